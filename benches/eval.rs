@@ -0,0 +1,174 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Compares evaluation on the arena layout against the id-keyed hash map
+//! layout the diagrams used before. Run with `cargo bench --bench eval`.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use flow::bdd::BinaryDecisionDiagram;
+use flow::Evaluate;
+
+/// Linear congruential generator, so runs are repeatable without extra
+/// dependencies.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        self.0 >> 33
+    }
+
+    fn bool(&mut self) -> bool { self.next() & 1 == 1 }
+}
+
+/// The node layout before the arena: nodes keyed by source id, with children
+/// looked up by id on every step.
+enum MapNode {
+    Decision(usize, usize, usize),
+    Terminal(bool),
+}
+
+struct MapDiagram {
+    nodes: HashMap<usize, MapNode>,
+    entry_node: usize,
+}
+
+impl MapDiagram {
+    fn from_text(text: &str) -> Self {
+        let mut nodes = HashMap::new();
+        let mut lines = text.lines().skip(2);
+        let entry_node = lines.next().unwrap()["root ".len()..].parse().unwrap();
+        for line in lines {
+            let fields: Vec<isize> = line
+                .split_whitespace()
+                .map(|field| field.parse().unwrap())
+                .collect();
+            let id = fields[0] as usize;
+            let node = if fields[1] < 0 {
+                MapNode::Terminal(fields[3] == 1)
+            } else {
+                MapNode::Decision(fields[3] as usize, fields[2] as usize, fields[1] as usize)
+            };
+            nodes.insert(id, node);
+        }
+        Self { nodes, entry_node }
+    }
+
+    fn eval(&self, values: &[bool]) -> bool {
+        let mut id = self.entry_node;
+        loop {
+            match self.nodes[&id] {
+                MapNode::Decision(variable_id, low, high) => {
+                    id = if values[variable_id] { high } else { low };
+                },
+                MapNode::Terminal(value) => return value,
+            }
+        }
+    }
+}
+
+/// A random function of `variables` inputs, built from its truth table.
+fn random_function(variables: usize, rng: &mut Lcg) -> BinaryDecisionDiagram {
+    let table: Vec<bool> = (0..1usize << variables).map(|_| rng.bool()).collect();
+    BinaryDecisionDiagram::from_truth_table(&table).unwrap()
+}
+
+/// Parity of `variables` inputs, written with scattered node ids the way a
+/// hand-edited or generated file might number them.
+fn parity(variables: usize) -> BinaryDecisionDiagram {
+    // Node `(level, odd)` is reached when an odd number of the variables
+    // before `level` are set.
+    let count = 2 * variables + 2;
+    let id = |level: usize, odd: bool| ((2 * level + usize::from(odd)) * 7919) % count;
+    let mut text = format!("vars {variables}\nnodes {}", 2 * variables + 1);
+    let _ = write!(
+        text,
+        "\n{} {} {} 0",
+        id(0, false),
+        id(1, true),
+        id(1, false)
+    );
+    for level in 1..variables {
+        for odd in [false, true] {
+            let _ = write!(
+                text,
+                "\n{} {} {} {level}",
+                id(level, odd),
+                id(level + 1, !odd),
+                id(level + 1, odd)
+            );
+        }
+    }
+    let _ = write!(text, "\n{} -1 -1 0", id(variables, false));
+    let _ = write!(text, "\n{} -1 -1 1", id(variables, true));
+    text.parse().unwrap()
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    run();
+    let start = Instant::now();
+    run();
+    start.elapsed()
+}
+
+fn bench(name: &str, bdd: &BinaryDecisionDiagram, assignments: usize, rng: &mut Lcg) {
+    let variables = bdd.variables();
+    let assignments: Vec<Vec<bool>> = (0..assignments)
+        .map(|_| (0..variables).map(|_| rng.bool()).collect())
+        .collect();
+    let baseline = MapDiagram::from_text(&bdd.to_string());
+
+    let map = time(|| {
+        for values in &assignments {
+            black_box(baseline.eval(black_box(values)));
+        }
+    });
+    let arena = time(|| {
+        for values in &assignments {
+            black_box(bdd.eval(black_box(values)).unwrap());
+        }
+    });
+    for values in assignments.iter().take(100) {
+        assert_eq!(baseline.eval(values), bdd.eval(values).unwrap());
+    }
+
+    println!(
+        "{name:<24} {variables:>6} vars {:>8} nodes  hash map {:>10.2?}  arena {:>10.2?}  ({:.1}x)",
+        bdd.node_count(),
+        map,
+        arena,
+        map.as_secs_f64() / arena.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = Lcg(0x5eed);
+    bench(
+        "random function",
+        &random_function(20, &mut rng),
+        20_000,
+        &mut rng,
+    );
+    bench("parity", &parity(4096), 2_000, &mut rng);
+    bench("parity", &parity(65_536), 100, &mut rng);
+}
@@ -0,0 +1,23 @@
+#![no_main]
+
+use flow::bdd::BinaryDecisionDiagram;
+use flow::{byte_to_bools, Evaluate};
+use libfuzzer_sys::fuzz_target;
+
+// The first eight bytes are the assignment, the rest is the diagram.
+fuzz_target!(|data: &[u8]| {
+    let (assignment, text) = data.split_at(data.len().min(8));
+    let Ok(text) = std::str::from_utf8(text) else {
+        return;
+    };
+    if let Ok(bdd) = text.parse::<BinaryDecisionDiagram>() {
+        let values: Vec<bool> = assignment
+            .iter()
+            .flat_map(|&byte| byte_to_bools(byte))
+            .collect();
+        let _ = bdd.eval(&values);
+        if bdd.variables() <= 12 {
+            let _ = bdd.truth_table();
+        }
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use flow::bdd::{AigerFormat, BinaryDecisionDiagram};
+use libfuzzer_sys::fuzz_target;
+
+// Every output that reads must write back out in both encodings.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(outputs) = BinaryDecisionDiagram::from_aiger(data) {
+        for format in [AigerFormat::Ascii, AigerFormat::Binary] {
+            let written = BinaryDecisionDiagram::to_aiger(&outputs, format)
+                .expect("diagrams read from AIGER must write");
+            BinaryDecisionDiagram::from_aiger(&written).expect("written AIGER must read");
+        }
+    }
+});
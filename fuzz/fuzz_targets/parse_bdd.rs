@@ -0,0 +1,13 @@
+#![no_main]
+
+use flow::bdd::BinaryDecisionDiagram;
+use libfuzzer_sys::fuzz_target;
+
+// Whatever parses must print as text that parses back to the same diagram.
+fuzz_target!(|text: &str| {
+    if let Ok(bdd) = text.parse::<BinaryDecisionDiagram>() {
+        let printed = bdd.to_string();
+        let reparsed: BinaryDecisionDiagram = printed.parse().expect("printed diagram must parse");
+        assert_eq!(printed, reparsed.to_string());
+    }
+});
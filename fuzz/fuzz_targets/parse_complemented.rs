@@ -0,0 +1,21 @@
+#![no_main]
+
+use flow::bdd::{BinaryDecisionDiagram, ComplementedDiagram};
+use flow::Evaluate;
+use libfuzzer_sys::fuzz_target;
+
+// Negation must flip every row of the truth table.
+fuzz_target!(|text: &str| {
+    if let Ok(diagram) = text.parse::<ComplementedDiagram>() {
+        if BinaryDecisionDiagram::from(&diagram).variables() > 12 {
+            return;
+        }
+        let table = diagram
+            .truth_table()
+            .expect("a parsed diagram must evaluate");
+        let negated = (!diagram)
+            .truth_table()
+            .expect("a negated diagram must evaluate");
+        assert!(table.iter().zip(&negated).all(|(a, b)| a != b));
+    }
+});
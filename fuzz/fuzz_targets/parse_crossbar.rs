@@ -0,0 +1,13 @@
+#![no_main]
+
+use flow::crossbar::CrossbarMatrix;
+use libfuzzer_sys::fuzz_target;
+
+// Whatever parses must print as text that parses back to the same crossbar.
+fuzz_target!(|text: &str| {
+    if let Ok(crossbar) = CrossbarMatrix::parse(text) {
+        let reparsed =
+            CrossbarMatrix::parse(&crossbar.to_string()).expect("printed crossbar must parse");
+        assert_eq!(crossbar, reparsed);
+    }
+});
@@ -0,0 +1,15 @@
+#![no_main]
+
+use flow::bdd::BinaryDecisionDiagram;
+use flow::Evaluate;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|text: &str| {
+    if let Ok(bdd) = BinaryDecisionDiagram::from_dimacs(text) {
+        let _ = bdd.model_count();
+        if let Ok(Some(model)) = bdd.model() {
+            assert_eq!(Ok(true), bdd.is_satisfiable());
+            assert_eq!(Ok(true), bdd.eval(&model));
+        }
+    }
+});
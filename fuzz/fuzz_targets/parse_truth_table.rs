@@ -0,0 +1,14 @@
+#![no_main]
+
+use flow::bdd::BinaryDecisionDiagram;
+use flow::Evaluate;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u8, &str)| {
+    let (variables, hex) = input;
+    if let Ok(bdd) = BinaryDecisionDiagram::from_hex_truth_table(hex, usize::from(variables)) {
+        assert_eq!(usize::from(variables), bdd.variables());
+        bdd.truth_table()
+            .expect("a diagram built from a table must evaluate");
+    }
+});
@@ -0,0 +1,76 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::add::{AddNode, AlgebraicDecisionDiagram, TerminalValue};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{convert_bits_to_bools, FlowError};
+
+impl<T: TerminalValue> AlgebraicDecisionDiagram<T> {
+    /// The value of the diagram under an assignment of the variables.
+    /// # Errors
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    pub fn value(&self, values: &[bool]) -> Result<T, FlowError> {
+        if values.len() < self.variables {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let mut id = self.root;
+        loop {
+            match &self.nodes[id] {
+                AddNode::Decision(node) => {
+                    id = if values[node.variable_id] {
+                        node.high
+                    } else {
+                        node.low
+                    };
+                },
+                AddNode::Terminal(value) => return Ok(*value),
+            }
+        }
+    }
+
+    /// The value for every assignment, indexed like
+    /// [`crate::Evaluate::truth_table`].
+    /// # Errors
+    /// * `EvaluationError` - there are too many variables to enumerate
+    pub fn value_table(&self) -> Result<Vec<T>, FlowError> {
+        if self.variables >= usize::BITS as usize {
+            return Err(EvaluationError("Too many variables"));
+        }
+        (0..1usize << self.variables)
+            .map(|var_set| self.value(&convert_bits_to_bools(var_set, self.variables)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::add::AlgebraicDecisionDiagram;
+
+    #[test]
+    fn value() {
+        let add = AlgebraicDecisionDiagram::variable(1, 2, -1, 9);
+        assert_eq!(Ok(9), add.value(&[false, true]));
+        assert_eq!(Ok(-1), add.value(&[true, false]));
+    }
+
+    #[test]
+    fn too_few_values() {
+        let add = AlgebraicDecisionDiagram::variable(1, 2, -1, 9);
+        assert!(add.value(&[true]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+/// A decision diagram whose terminals hold numbers instead of booleans, so it
+/// can represent costs or probabilities over the same variables as a
+/// [`crate::bdd::BinaryDecisionDiagram`].
+#[derive(Clone, Debug)]
+pub struct AlgebraicDecisionDiagram<T: TerminalValue> {
+    variables: usize,
+    nodes: Vec<AddNode<T>>,
+    unique: HashMap<(usize, usize, usize), usize>,
+    terminals: HashMap<T::Key, usize>,
+    root: usize,
+}
+
+mod eval;
+mod ops;
+
+/// Numbers that can be stored in the terminals of an
+/// [`AlgebraicDecisionDiagram`].
+pub trait TerminalValue: Copy + Debug + PartialOrd {
+    type Key: Copy + Debug + Eq + std::hash::Hash;
+
+    /// A hashable key such that equal values share a terminal.
+    fn key(self) -> Self::Key;
+
+    /// The sum, or `None` when it cannot be represented.
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// The product, or `None` when it cannot be represented.
+    fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+impl TerminalValue for i64 {
+    type Key = i64;
+
+    fn key(self) -> i64 { self }
+
+    fn checked_add(self, other: Self) -> Option<Self> { i64::checked_add(self, other) }
+
+    fn checked_mul(self, other: Self) -> Option<Self> { i64::checked_mul(self, other) }
+}
+
+impl TerminalValue for f64 {
+    type Key = u64;
+
+    /// The bit pattern, with `-0.0` folded into `0.0`.
+    fn key(self) -> u64 { (self + 0.0).to_bits() }
+
+    /// Always the sum, which is infinite when it overflows.
+    fn checked_add(self, other: Self) -> Option<Self> { Some(self + other) }
+
+    /// Always the product, which is infinite when it overflows.
+    fn checked_mul(self, other: Self) -> Option<Self> { Some(self * other) }
+}
+
+/// Binary operators on the terminals of two algebraic diagrams.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AddOperator {
+    Plus,
+    Times,
+    Max,
+    Min,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum AddNode<T> {
+    Decision(DecisionNode),
+    Terminal(T),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct DecisionNode {
+    variable_id: usize,
+    low: usize,
+    high: usize,
+}
+
+impl<T: TerminalValue> AlgebraicDecisionDiagram<T> {
+    fn with_variables(variables: usize) -> Self {
+        Self {
+            variables,
+            nodes: Vec::new(),
+            unique: HashMap::new(),
+            terminals: HashMap::new(),
+            root: 0,
+        }
+    }
+
+    /// A diagram over `variables` variables with the same value everywhere.
+    #[must_use]
+    pub fn constant(value: T, variables: usize) -> Self {
+        let mut add = Self::with_variables(variables);
+        add.root = add.terminal(value);
+        add
+    }
+
+    /// A diagram that is `high` when `variable_id` is true and `low` otherwise.
+    #[must_use]
+    pub fn variable(variable_id: usize, variables: usize, low: T, high: T) -> Self {
+        let mut add = Self::with_variables(variables.max(variable_id + 1));
+        let low = add.terminal(low);
+        let high = add.terminal(high);
+        add.root = add.mk(variable_id, low, high);
+        add
+    }
+
+    /// The number of variables the diagram is declared over.
+    #[must_use]
+    pub fn variables(&self) -> usize { self.variables }
+
+    /// The number of nodes reachable from the root, terminals included.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root];
+        let mut count = 0;
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            count += 1;
+            if let AddNode::Decision(node) = &self.nodes[id] {
+                stack.push(node.low);
+                stack.push(node.high);
+            }
+        }
+        count
+    }
+
+    fn terminal(&mut self, value: T) -> usize {
+        if let Some(&id) = self.terminals.get(&value.key()) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(AddNode::Terminal(value));
+        self.terminals.insert(value.key(), id);
+        id
+    }
+
+    /// Find or create the node `(variable, low, high)`, skipping redundant
+    /// tests.
+    fn mk(&mut self, variable_id: usize, low: usize, high: usize) -> usize {
+        if low == high {
+            return low;
+        }
+        if let Some(&id) = self.unique.get(&(variable_id, low, high)) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(AddNode::Decision(DecisionNode {
+            variable_id,
+            low,
+            high,
+        }));
+        self.unique.insert((variable_id, low, high), id);
+        id
+    }
+
+    fn top_variable(&self, id: usize) -> usize {
+        match &self.nodes[id] {
+            AddNode::Decision(node) => node.variable_id,
+            AddNode::Terminal(_) => usize::MAX,
+        }
+    }
+
+    fn cofactors(&self, id: usize, variable_id: usize) -> (usize, usize) {
+        match &self.nodes[id] {
+            AddNode::Decision(node) if node.variable_id == variable_id => (node.low, node.high),
+            _ => (id, id),
+        }
+    }
+
+    /// Copy the nodes of `other` reachable from `id` into this diagram.
+    fn import(&mut self, other: &Self, id: usize, imported: &mut HashMap<usize, usize>) -> usize {
+        if let Some(&new_id) = imported.get(&id) {
+            return new_id;
+        }
+        let new_id = match &other.nodes[id] {
+            AddNode::Terminal(value) => self.terminal(*value),
+            AddNode::Decision(node) => {
+                let (variable_id, low, high) = (node.variable_id, node.low, node.high);
+                let low = self.import(other, low, imported);
+                let high = self.import(other, high, imported);
+                self.mk(variable_id, low, high)
+            },
+        };
+        imported.insert(id, new_id);
+        new_id
+    }
+
+    /// A copy of this diagram rooted at `root`, without unreachable nodes.
+    fn rooted_at(&self, root: usize) -> Self {
+        let mut add = Self::with_variables(self.variables);
+        add.root = add.import(self, root, &mut HashMap::new());
+        add
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::add::AlgebraicDecisionDiagram;
+
+    #[test]
+    fn terminals_shared() {
+        let mut add = AlgebraicDecisionDiagram::constant(1.5, 1);
+        let root = add.root;
+        assert_eq!(root, add.terminal(1.5));
+        assert_eq!(add.terminal(0.0), add.terminal(-0.0));
+    }
+
+    #[test]
+    fn redundant_test_skipped() {
+        let add = AlgebraicDecisionDiagram::variable(0, 1, 3, 3);
+        assert_eq!(1, add.node_count());
+    }
+
+    #[test]
+    fn variable() {
+        let add = AlgebraicDecisionDiagram::variable(1, 1, 0, 7);
+        assert_eq!(2, add.variables());
+        assert_eq!(3, add.node_count());
+    }
+}
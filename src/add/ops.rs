@@ -0,0 +1,274 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::add::{AddNode, AddOperator, AlgebraicDecisionDiagram, TerminalValue};
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE};
+use crate::bdd::BinaryDecisionDiagram;
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+impl AddOperator {
+    /// Apply the operator to a pair of terminal values.
+    /// # Errors
+    /// * `EvaluationError` - the result does not fit in the terminal type
+    pub fn eval<T: TerminalValue>(self, left: T, right: T) -> Result<T, FlowError> {
+        match self {
+            Self::Plus => left
+                .checked_add(right)
+                .ok_or(EvaluationError("The sum of two terminals overflows")),
+            Self::Times => left
+                .checked_mul(right)
+                .ok_or(EvaluationError("The product of two terminals overflows")),
+            Self::Max => Ok(if right > left { right } else { left }),
+            Self::Min => Ok(if right < left { right } else { left }),
+        }
+    }
+}
+
+impl<T: TerminalValue> AlgebraicDecisionDiagram<T> {
+    /// Lift a binary diagram, mapping its false terminal to `low` and its true
+    /// terminal to `high`.
+    /// # Errors
+    /// * `EvaluationError` - the diagram references a missing node or contains
+    ///   a cycle
+    /// # Example
+    /// ```
+    /// use flow::add::AlgebraicDecisionDiagram;
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// let bdd = BinaryDecisionDiagram::variable(0, 1);
+    /// let cost = AlgebraicDecisionDiagram::from_bdd(&bdd, 0.0, 2.5).unwrap();
+    /// assert_eq!(2.5, cost.value(&[true]).unwrap());
+    /// ```
+    pub fn from_bdd(bdd: &BinaryDecisionDiagram, low: T, high: T) -> Result<Self, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(bdd)?;
+
+        let mut add = Self::with_variables(bdd.variables());
+        let mut imported: HashMap<usize, usize> = HashMap::new();
+        add.root = add.import_bdd(&builder, root, low, high, &mut imported);
+        Ok(add)
+    }
+
+    fn import_bdd(
+        &mut self,
+        builder: &DiagramBuilder,
+        id: usize,
+        low: T,
+        high: T,
+        imported: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if let Some(&new_id) = imported.get(&id) {
+            return new_id;
+        }
+        let new_id = match builder.decision(id) {
+            Some((variable_id, low_id, high_id)) => {
+                self.variables = self.variables.max(variable_id + 1);
+                let low_id = self.import_bdd(builder, low_id, low, high, imported);
+                let high_id = self.import_bdd(builder, high_id, low, high, imported);
+                self.mk(variable_id, low_id, high_id)
+            },
+            None if id == FALSE_NODE => self.terminal(low),
+            None => self.terminal(high),
+        };
+        imported.insert(id, new_id);
+        new_id
+    }
+
+    /// Combine two diagrams terminal by terminal.
+    /// # Errors
+    /// * `EvaluationError` - a sum or product of `i64` terminals overflows
+    /// # Example
+    /// ```
+    /// use flow::add::{AddOperator, AlgebraicDecisionDiagram};
+    /// let x = AlgebraicDecisionDiagram::variable(0, 2, 0, 1);
+    /// let y = AlgebraicDecisionDiagram::variable(1, 2, 0, 2);
+    /// let sum = x.apply(&y, AddOperator::Plus).unwrap();
+    /// assert_eq!(vec![0, 1, 2, 3], sum.value_table().unwrap());
+    /// ```
+    pub fn apply(&self, other: &Self, operator: AddOperator) -> Result<Self, FlowError> {
+        let mut add = self.clone();
+        add.variables = self.variables.max(other.variables);
+        let right = add.import(other, other.root, &mut HashMap::new());
+        let root = add.apply_nodes(operator, self.root, right, &mut HashMap::new())?;
+        Ok(add.rooted_at(root))
+    }
+
+    fn apply_nodes(
+        &mut self,
+        operator: AddOperator,
+        f: usize,
+        g: usize,
+        cache: &mut HashMap<(usize, usize), usize>,
+    ) -> Result<usize, FlowError> {
+        if let (AddNode::Terminal(left), AddNode::Terminal(right)) =
+            (&self.nodes[f], &self.nodes[g])
+        {
+            let value = operator.eval(*left, *right)?;
+            return Ok(self.terminal(value));
+        }
+        if let Some(&id) = cache.get(&(f, g)) {
+            return Ok(id);
+        }
+        let top = self.top_variable(f).min(self.top_variable(g));
+        let (f_low, f_high) = self.cofactors(f, top);
+        let (g_low, g_high) = self.cofactors(g, top);
+        let low = self.apply_nodes(operator, f_low, g_low, cache)?;
+        let high = self.apply_nodes(operator, f_high, g_high, cache)?;
+        let id = self.mk(top, low, high);
+        cache.insert((f, g), id);
+        Ok(id)
+    }
+
+    /// The binary diagram that is true wherever the value is at least
+    /// `threshold`.
+    #[must_use]
+    pub fn threshold(&self, threshold: T) -> BinaryDecisionDiagram {
+        let mut builder = DiagramBuilder::new();
+        let mut converted: HashMap<usize, usize> = HashMap::new();
+        let root = self.threshold_node(&mut builder, self.root, threshold, &mut converted);
+        builder.build(root, self.variables)
+    }
+
+    fn threshold_node(
+        &self,
+        builder: &mut DiagramBuilder,
+        id: usize,
+        threshold: T,
+        converted: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if let Some(&bdd_id) = converted.get(&id) {
+            return bdd_id;
+        }
+        let bdd_id = match &self.nodes[id] {
+            AddNode::Terminal(value) => DiagramBuilder::constant(*value >= threshold),
+            AddNode::Decision(node) => {
+                let low = self.threshold_node(builder, node.low, threshold, converted);
+                let high = self.threshold_node(builder, node.high, threshold, converted);
+                builder.mk(node.variable_id, low, high)
+            },
+        };
+        converted.insert(id, bdd_id);
+        bdd_id
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::add::{AddOperator, AlgebraicDecisionDiagram};
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::Evaluate;
+
+    fn costs() -> (AlgebraicDecisionDiagram<i64>, AlgebraicDecisionDiagram<i64>) {
+        (
+            AlgebraicDecisionDiagram::variable(0, 2, 1, 4),
+            AlgebraicDecisionDiagram::variable(1, 2, 3, 2),
+        )
+    }
+
+    #[test]
+    fn plus() {
+        let (x, y) = costs();
+        assert_eq!(
+            vec![4, 7, 3, 6],
+            x.apply(&y, AddOperator::Plus)
+                .unwrap()
+                .value_table()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn times() {
+        let (x, y) = costs();
+        assert_eq!(
+            vec![3, 12, 2, 8],
+            x.apply(&y, AddOperator::Times)
+                .unwrap()
+                .value_table()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn max_and_min() {
+        let (x, y) = costs();
+        assert_eq!(
+            vec![3, 4, 2, 4],
+            x.apply(&y, AddOperator::Max)
+                .unwrap()
+                .value_table()
+                .unwrap()
+        );
+        assert_eq!(
+            vec![1, 3, 1, 2],
+            x.apply(&y, AddOperator::Min)
+                .unwrap()
+                .value_table()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn overflow() {
+        let max = AlgebraicDecisionDiagram::variable(0, 1, 0, i64::MAX);
+        let one = AlgebraicDecisionDiagram::constant(1, 1);
+        assert!(max.apply(&one, AddOperator::Plus).is_err());
+        assert!(max.apply(&max, AddOperator::Times).is_err());
+        assert_eq!(
+            vec![1, i64::MAX],
+            max.apply(&one, AddOperator::Max)
+                .unwrap()
+                .value_table()
+                .unwrap()
+        );
+        let min = AlgebraicDecisionDiagram::constant(i64::MIN, 1);
+        assert!(min.apply(&min, AddOperator::Plus).is_err());
+
+        // Floating-point terminals overflow to infinity instead.
+        let large = AlgebraicDecisionDiagram::constant(f64::MAX, 1);
+        let sum = large.apply(&large, AddOperator::Plus).unwrap();
+        assert_eq!(vec![f64::INFINITY; 2], sum.value_table().unwrap());
+    }
+
+    #[test]
+    fn probabilities() {
+        let x = AlgebraicDecisionDiagram::variable(0, 1, 0.25, 0.75);
+        let not_x = AlgebraicDecisionDiagram::variable(0, 1, 0.75, 0.25);
+        let sum = x.apply(&not_x, AddOperator::Plus).unwrap();
+        assert_eq!(1, sum.node_count());
+        assert_eq!(vec![1.0, 1.0], sum.value_table().unwrap());
+    }
+
+    #[test]
+    fn threshold() {
+        let (x, y) = costs();
+        let sum = x.apply(&y, AddOperator::Plus).unwrap();
+        assert_eq!(
+            vec![true, true, false, true],
+            sum.threshold(4).truth_table().unwrap()
+        );
+    }
+
+    #[test]
+    fn from_bdd() {
+        let bdd = BinaryDecisionDiagram::from_truth_table(&[false, true, true, false]).unwrap();
+        let add = AlgebraicDecisionDiagram::from_bdd(&bdd, 0, 5).unwrap();
+        assert_eq!(vec![0, 5, 5, 0], add.value_table().unwrap());
+        assert_eq!(bdd.truth_table(), add.threshold(1).truth_table());
+    }
+}
@@ -0,0 +1,563 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE};
+use crate::bdd::BinaryNode::{Decision, Terminal};
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator, NodeIndex};
+use crate::FlowError;
+use crate::FlowError::{EvaluationError, ParseError};
+
+/// The two encodings of the AIGER format.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AigerFormat {
+    /// `aag`, one gate per line
+    Ascii,
+    /// `aig`, delta-encoded gates
+    Binary,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Definition {
+    Input(usize),
+    And(usize, usize),
+}
+
+impl BinaryDecisionDiagram {
+    /// Read a combinational and-inverter graph in either AIGER encoding. Each
+    /// output becomes a diagram over the graph's inputs, with input `k` as
+    /// variable `k`.
+    /// # Errors
+    /// * `ParseError` - the header or a gate is malformed, or the graph has
+    ///   latches
+    /// * `EvaluationError` - a literal is undefined or the gates form a cycle
+    /// # Example
+    /// ```
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// use flow::Evaluate;
+    /// const AND: &str = "aag 3 2 0 1 1
+    /// 2
+    /// 4
+    /// 6
+    /// 6 2 4";
+    /// let outputs = BinaryDecisionDiagram::from_aiger(AND.as_bytes()).unwrap();
+    /// assert_eq!(
+    ///     vec![false, false, false, true],
+    ///     outputs[0].truth_table().unwrap()
+    /// );
+    /// ```
+    pub fn from_aiger(source: &[u8]) -> Result<Vec<Self>, FlowError> {
+        let mut reader = Reader {
+            source,
+            position: 0,
+        };
+        let header = reader.line()?;
+        let mut header = header.split_ascii_whitespace();
+        let format = match header.next() {
+            Some("aag") => AigerFormat::Ascii,
+            Some("aig") => AigerFormat::Binary,
+            _ => return Err(ParseError("Header does not start with aag or aig")),
+        };
+        let mut counts = [0usize; 5];
+        for count in &mut counts {
+            *count = header
+                .next()
+                .ok_or(ParseError("Header does not specify M I L O A"))?
+                .parse()?;
+        }
+        for extension in header {
+            if extension.parse::<usize>()? != 0 {
+                return Err(ParseError("Only combinational AIGER files are supported"));
+            }
+        }
+        let [max_var, num_inputs, num_latches, num_outputs, num_ands] = counts;
+        if num_latches != 0 {
+            return Err(ParseError("Latches are not supported"));
+        }
+        if format == AigerFormat::Binary && Some(max_var) != num_inputs.checked_add(num_ands) {
+            return Err(ParseError("Binary header must satisfy M = I + L + A"));
+        }
+        // Every listed input and gate takes at least one byte, so a larger
+        // header cannot describe this file. Binary inputs are implicit.
+        let listed = match format {
+            AigerFormat::Ascii => num_inputs.saturating_add(num_ands),
+            AigerFormat::Binary => num_ands,
+        };
+        if listed > source.len() || max_var >= usize::MAX / 2 {
+            return Err(ParseError("Header counts exceed the size of the file"));
+        }
+
+        let mut definitions: HashMap<usize, Definition> = HashMap::new();
+        if format == AigerFormat::Ascii {
+            for input in 0..num_inputs {
+                let literal = reader.literal(max_var)?;
+                if literal & 1 == 1 {
+                    return Err(ParseError("Input literal must not be negated"));
+                }
+                define(
+                    &mut definitions,
+                    max_var,
+                    literal >> 1,
+                    Definition::Input(input),
+                )?;
+            }
+        }
+        // Binary inputs are the variables 1 to I, in order.
+        let implicit_inputs = match format {
+            AigerFormat::Ascii => 0,
+            AigerFormat::Binary => num_inputs,
+        };
+        let outputs = (0..num_outputs)
+            .map(|_| reader.literal(max_var))
+            .collect::<Result<Vec<_>, _>>()?;
+        for gate in 0..num_ands {
+            let (lhs, rhs0, rhs1) = match format {
+                AigerFormat::Ascii => {
+                    let line = reader.line()?;
+                    let mut split = line.split_ascii_whitespace();
+                    let mut literal = || -> Result<usize, FlowError> {
+                        check_literal(
+                            split
+                                .next()
+                                .ok_or(ParseError("And gate needs three literals"))?
+                                .parse()?,
+                            max_var,
+                        )
+                    };
+                    (literal()?, literal()?, literal()?)
+                },
+                AigerFormat::Binary => {
+                    let lhs = 2 * (num_inputs + gate + 1);
+                    let rhs0 = lhs
+                        .checked_sub(reader.delta()?)
+                        .ok_or(ParseError("And gate delta is out of range"))?;
+                    let rhs1 = rhs0
+                        .checked_sub(reader.delta()?)
+                        .ok_or(ParseError("And gate delta is out of range"))?;
+                    (lhs, rhs0, rhs1)
+                },
+            };
+            if lhs & 1 == 1 {
+                return Err(ParseError("And gate output must not be negated"));
+            }
+            define(
+                &mut definitions,
+                max_var,
+                lhs >> 1,
+                Definition::And(rhs0, rhs1),
+            )?;
+        }
+
+        let mut builder = DiagramBuilder::new();
+        let mut resolved: HashMap<usize, usize> = HashMap::from([(0, FALSE_NODE)]);
+        let mut in_progress: HashSet<usize> = HashSet::new();
+        outputs
+            .into_iter()
+            .map(|literal| {
+                let root = resolve(
+                    &mut builder,
+                    &definitions,
+                    implicit_inputs,
+                    &mut resolved,
+                    &mut in_progress,
+                    literal,
+                )?;
+                Ok(builder.build(root, num_inputs))
+            })
+            .collect()
+    }
+
+    /// Write diagrams as a combinational and-inverter graph with one output
+    /// per diagram. Every decision node becomes a multiplexer of and gates,
+    /// with variable `k` as input `k`.
+    /// # Errors
+    /// * `EvaluationError` - a diagram references a missing node or contains a
+    ///   cycle
+    pub fn to_aiger(diagrams: &[Self], format: AigerFormat) -> Result<Vec<u8>, FlowError> {
+        let num_inputs = diagrams
+            .iter()
+            .flat_map(|bdd| {
+                bdd.nodes.iter().map(|node| match node {
+                    Decision(node) => node.variable_id + 1,
+                    Terminal(_) => bdd.variables,
+                })
+            })
+            .chain(diagrams.iter().map(|bdd| bdd.variables))
+            .max()
+            .unwrap_or(0);
+        let mut graph = AndInverterGraph {
+            num_inputs,
+            gates: Vec::new(),
+            cache: HashMap::new(),
+        };
+        let outputs = diagrams
+            .iter()
+            .map(|bdd| graph.add_diagram(bdd))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let magic = match format {
+            AigerFormat::Ascii => "aag",
+            AigerFormat::Binary => "aig",
+        };
+        let num_ands = graph.gates.len();
+        let mut out = format!(
+            "{magic} {} {num_inputs} 0 {} {num_ands}\n",
+            num_inputs + num_ands,
+            outputs.len()
+        );
+        if format == AigerFormat::Ascii {
+            for input in 0..num_inputs {
+                out.push_str(&format!("{}\n", 2 * (input + 1)));
+            }
+        }
+        for output in &outputs {
+            out.push_str(&format!("{output}\n"));
+        }
+
+        let mut bytes = match format {
+            AigerFormat::Ascii => {
+                for (lhs, rhs0, rhs1) in &graph.gates {
+                    out.push_str(&format!("{lhs} {rhs0} {rhs1}\n"));
+                }
+                out.into_bytes()
+            },
+            AigerFormat::Binary => {
+                let mut bytes = out.into_bytes();
+                for &(lhs, rhs0, rhs1) in &graph.gates {
+                    encode_delta(&mut bytes, lhs - rhs0);
+                    encode_delta(&mut bytes, rhs0 - rhs1);
+                }
+                bytes
+            },
+        };
+        for input in 0..num_inputs {
+            bytes.extend(format!("i{input} x{input}\n").bytes());
+        }
+        Ok(bytes)
+    }
+}
+
+fn check_literal(literal: usize, max_var: usize) -> Result<usize, FlowError> {
+    if literal >> 1 > max_var {
+        return Err(ParseError("Literal exceeds maximum variable index"));
+    }
+    Ok(literal)
+}
+
+fn define(
+    definitions: &mut HashMap<usize, Definition>,
+    max_var: usize,
+    variable: usize,
+    definition: Definition,
+) -> Result<(), FlowError> {
+    if variable == 0 {
+        return Err(ParseError("Constant literal cannot be redefined"));
+    }
+    if variable > max_var {
+        return Err(ParseError("Literal exceeds maximum variable index"));
+    }
+    if definitions.insert(variable, definition).is_some() {
+        return Err(ParseError("Literal is defined more than once"));
+    }
+    Ok(())
+}
+
+/// Translate an AIG literal into a builder node, resolving its fan-in first.
+/// Variables `1..=implicit_inputs` without a definition are inputs.
+fn resolve(
+    builder: &mut DiagramBuilder,
+    definitions: &HashMap<usize, Definition>,
+    implicit_inputs: usize,
+    resolved: &mut HashMap<usize, usize>,
+    in_progress: &mut HashSet<usize>,
+    literal: usize,
+) -> Result<usize, FlowError> {
+    let mut stack = vec![(literal >> 1, false)];
+    while let Some((variable, expanded)) = stack.pop() {
+        if resolved.contains_key(&variable) {
+            continue;
+        }
+        let definition = match definitions.get(&variable) {
+            Some(&definition) => definition,
+            None if (1..=implicit_inputs).contains(&variable) => Definition::Input(variable - 1),
+            None => return Err(EvaluationError("Literal is never defined")),
+        };
+        match definition {
+            Definition::Input(input) => {
+                resolved.insert(variable, builder.variable(input));
+            },
+            Definition::And(rhs0, rhs1) if expanded => {
+                let left = resolve_literal(builder, resolved, rhs0);
+                let right = resolve_literal(builder, resolved, rhs1);
+                resolved.insert(variable, builder.apply(BinaryOperator::And, left, right));
+            },
+            Definition::And(rhs0, rhs1) => {
+                if !in_progress.insert(variable) {
+                    return Err(EvaluationError("And gates form a cycle"));
+                }
+                stack.push((variable, true));
+                stack.push((rhs0 >> 1, false));
+                stack.push((rhs1 >> 1, false));
+            },
+        }
+    }
+    Ok(resolve_literal(builder, resolved, literal))
+}
+
+fn resolve_literal(
+    builder: &mut DiagramBuilder,
+    resolved: &HashMap<usize, usize>,
+    literal: usize,
+) -> usize {
+    let node = resolved.get(&(literal >> 1)).copied().unwrap_or(FALSE_NODE);
+    if literal & 1 == 1 {
+        builder.not(node)
+    } else {
+        node
+    }
+}
+
+fn encode_delta(bytes: &mut Vec<u8>, mut delta: usize) {
+    while delta >= 0x80 {
+        #[allow(clippy::cast_possible_truncation)]
+        bytes.push((delta & 0x7f) as u8 | 0x80);
+        delta >>= 7;
+    }
+    #[allow(clippy::cast_possible_truncation)]
+    bytes.push(delta as u8);
+}
+
+struct Reader<'a> {
+    source: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn line(&mut self) -> Result<&'a str, FlowError> {
+        let rest = &self.source[self.position..];
+        if rest.is_empty() {
+            return Err(ParseError("Unexpected end of file"));
+        }
+        let end = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.position += (end + 1).min(rest.len());
+        std::str::from_utf8(&rest[..end]).map_err(|_| ParseError("Line is not valid UTF-8"))
+    }
+
+    fn literal(&mut self, max_var: usize) -> Result<usize, FlowError> {
+        let literal = self
+            .line()?
+            .split_ascii_whitespace()
+            .next()
+            .ok_or(ParseError("Literal not present"))?
+            .parse()?;
+        check_literal(literal, max_var)
+    }
+
+    fn delta(&mut self) -> Result<usize, FlowError> {
+        let mut delta: usize = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *self
+                .source
+                .get(self.position)
+                .ok_or(ParseError("Unexpected end of file"))?;
+            self.position += 1;
+            if shift >= usize::BITS {
+                return Err(ParseError("And gate delta is out of range"));
+            }
+            delta |= usize::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(delta);
+            }
+            shift += 7;
+        }
+    }
+}
+
+/// An and-inverter graph under construction, with structural hashing of its
+/// gates.
+struct AndInverterGraph {
+    num_inputs: usize,
+    gates: Vec<(usize, usize, usize)>,
+    cache: HashMap<(usize, usize), usize>,
+}
+
+impl AndInverterGraph {
+    fn and(&mut self, left: usize, right: usize) -> usize {
+        let (rhs0, rhs1) = (left.max(right), left.min(right));
+        if rhs1 == 0 || rhs0 == rhs1 ^ 1 {
+            return 0;
+        }
+        if rhs1 == 1 || rhs0 == rhs1 {
+            return rhs0;
+        }
+        if let Some(&lhs) = self.cache.get(&(rhs0, rhs1)) {
+            return lhs;
+        }
+        let lhs = 2 * (self.num_inputs + self.gates.len() + 1);
+        self.gates.push((lhs, rhs0, rhs1));
+        self.cache.insert((rhs0, rhs1), lhs);
+        lhs
+    }
+
+    fn mux(&mut self, select: usize, high: usize, low: usize) -> usize {
+        let when_high = self.and(select, high);
+        let when_low = self.and(select ^ 1, low);
+        self.and(when_high ^ 1, when_low ^ 1) ^ 1
+    }
+
+    /// Add the gates for a diagram, returning the literal of its root.
+    fn add_diagram(&mut self, bdd: &BinaryDecisionDiagram) -> Result<usize, FlowError> {
+        let mut literals: HashMap<NodeIndex, usize> = HashMap::new();
+        let mut in_progress: HashMap<NodeIndex, bool> = HashMap::new();
+        let mut stack = vec![(bdd.entry_node, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if literals.contains_key(&id) {
+                continue;
+            }
+            match bdd
+                .nodes
+                .get(id.0)
+                .ok_or(EvaluationError("Could not traverse to next node"))?
+            {
+                Terminal(value) => {
+                    literals.insert(id, usize::from(*value));
+                },
+                Decision(node) if expanded => {
+                    let (low, high) = node.decision_map;
+                    let select = 2 * (node.variable_id + 1);
+                    let literal = self.mux(select, literals[&high], literals[&low]);
+                    literals.insert(id, literal);
+                },
+                Decision(node) => {
+                    if in_progress.insert(id, true).is_some() {
+                        return Err(EvaluationError("The diagram contains a cycle"));
+                    }
+                    stack.push((id, true));
+                    stack.push((node.decision_map.0, false));
+                    stack.push((node.decision_map.1, false));
+                },
+            }
+        }
+        Ok(literals[&bdd.entry_node])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::{AigerFormat, BinaryDecisionDiagram};
+    use crate::Evaluate;
+
+    const AND_AAG: &str = "aag 3 2 0 1 1
+2
+4
+6
+6 2 4
+";
+
+    const XOR_BDD: &str = "vars 2
+nodes 5
+0 2 1 0
+1 4 3 1
+2 3 4 1
+3 -1 -1 0
+4 -1 -1 1";
+
+    #[test]
+    fn read_ascii() {
+        let outputs = BinaryDecisionDiagram::from_aiger(AND_AAG.as_bytes()).unwrap();
+        assert_eq!(1, outputs.len());
+        assert_eq!(
+            vec![false, false, false, true],
+            outputs[0].truth_table().unwrap()
+        );
+    }
+
+    #[test]
+    fn read_binary() {
+        let mut source = b"aig 3 2 0 2 1\n6\n7\n".to_vec();
+        source.extend([0x02, 0x02]);
+        let outputs = BinaryDecisionDiagram::from_aiger(&source).unwrap();
+        assert_eq!(
+            vec![false, false, false, true],
+            outputs[0].truth_table().unwrap()
+        );
+        assert_eq!(
+            vec![true, true, true, false],
+            outputs[1].truth_table().unwrap()
+        );
+    }
+
+    #[test]
+    fn constant_outputs() {
+        let outputs = BinaryDecisionDiagram::from_aiger(b"aag 0 0 0 2 0\n0\n1\n").unwrap();
+        assert_eq!(vec![false], outputs[0].truth_table().unwrap());
+        assert_eq!(vec![true], outputs[1].truth_table().unwrap());
+    }
+
+    #[test]
+    fn latches() {
+        let source = b"aag 1 0 1 0 0\n2 3\n";
+        assert!(BinaryDecisionDiagram::from_aiger(source).is_err());
+    }
+
+    #[test]
+    fn cycle() {
+        let source = b"aag 3 1 0 1 2\n2\n4\n4 2 6\n6 2 4\n";
+        assert!(BinaryDecisionDiagram::from_aiger(source).is_err());
+    }
+
+    #[test]
+    fn undefined_literal() {
+        let source = b"aag 2 1 0 1 0\n2\n4\n";
+        assert!(BinaryDecisionDiagram::from_aiger(source).is_err());
+    }
+
+    #[test]
+    fn huge_header() {
+        let source = format!("aag {0} {0} 0 0 0\n", usize::MAX);
+        assert!(BinaryDecisionDiagram::from_aiger(source.as_bytes()).is_err());
+        let source = format!("aig {0} 0 0 1 {0}\n2\n", usize::MAX - 1);
+        assert!(BinaryDecisionDiagram::from_aiger(source.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn round_trip() {
+        let bdd: BinaryDecisionDiagram = XOR_BDD.parse().unwrap();
+        for format in [AigerFormat::Ascii, AigerFormat::Binary] {
+            let written =
+                BinaryDecisionDiagram::to_aiger(&[bdd.negate().unwrap(), bdd.clone()], format)
+                    .unwrap();
+            let outputs = BinaryDecisionDiagram::from_aiger(&written).unwrap();
+            assert_eq!(
+                vec![true, false, false, true],
+                outputs[0].truth_table().unwrap()
+            );
+            assert_eq!(bdd.truth_table(), outputs[1].truth_table());
+        }
+    }
+
+    #[test]
+    fn write_ascii() {
+        let bdd = BinaryDecisionDiagram::variable(0, 1);
+        let written = BinaryDecisionDiagram::to_aiger(&[bdd], AigerFormat::Ascii).unwrap();
+        assert_eq!(
+            "aag 1 1 0 1 0\n2\n2\ni0 x0\n",
+            String::from_utf8(written).unwrap()
+        );
+    }
+}
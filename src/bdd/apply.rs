@@ -0,0 +1,147 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bdd::builder::DiagramBuilder;
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+use crate::FlowError;
+
+impl BinaryOperator {
+    /// Apply the operator to a pair of booleans.
+    #[must_use]
+    pub fn eval(self, left: bool, right: bool) -> bool {
+        match self {
+            Self::And => left && right,
+            Self::Or => left || right,
+            Self::Xor => left ^ right,
+            Self::Nand => !(left && right),
+            Self::Nor => !(left || right),
+            Self::Xnor => left == right,
+            Self::Implies => !left || right,
+        }
+    }
+}
+
+impl BinaryDecisionDiagram {
+    /// A diagram over `variables` variables that always evaluates to `value`.
+    #[must_use]
+    pub fn constant(value: bool, variables: usize) -> Self {
+        DiagramBuilder::new().build(DiagramBuilder::constant(value), variables)
+    }
+
+    /// A diagram over `variables` variables that evaluates to the value of
+    /// `variable_id`.
+    #[must_use]
+    pub fn variable(variable_id: usize, variables: usize) -> Self {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.variable(variable_id);
+        builder.build(root, variables)
+    }
+
+    /// Combine two diagrams with a boolean operator. The result is reduced and
+    /// ordered by ascending variable id.
+    /// # Errors
+    /// * `EvaluationError` - either diagram references a missing node or
+    ///   contains a cycle
+    /// # Example
+    /// ```
+    /// use flow::bdd::{BinaryDecisionDiagram, BinaryOperator};
+    /// use flow::Evaluate;
+    /// let x = BinaryDecisionDiagram::variable(0, 2);
+    /// let y = BinaryDecisionDiagram::variable(1, 2);
+    /// let and = x.apply(&y, BinaryOperator::And).unwrap();
+    /// assert_eq!(vec![false, false, false, true], and.truth_table().unwrap());
+    /// ```
+    pub fn apply(&self, other: &Self, operator: BinaryOperator) -> Result<Self, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let left = builder.import(self)?;
+        let right = builder.import(other)?;
+        let root = builder.apply(operator, left, right);
+        Ok(builder.build(root, self.variables.max(other.variables)))
+    }
+
+    /// The complement of this diagram.
+    /// # Errors
+    /// * `EvaluationError` - the diagram references a missing node or contains
+    ///   a cycle
+    pub fn negate(&self) -> Result<Self, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(self)?;
+        let root = builder.not(root);
+        Ok(builder.build(root, self.variables))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+    use crate::Evaluate;
+
+    const OPERATORS: [BinaryOperator; 7] = [
+        BinaryOperator::And,
+        BinaryOperator::Or,
+        BinaryOperator::Xor,
+        BinaryOperator::Nand,
+        BinaryOperator::Nor,
+        BinaryOperator::Xnor,
+        BinaryOperator::Implies,
+    ];
+
+    #[test]
+    fn apply_matches_pointwise() {
+        let x = BinaryDecisionDiagram::variable(0, 2);
+        let y = BinaryDecisionDiagram::variable(1, 2);
+        for operator in OPERATORS {
+            let expected: Vec<bool> = (0..4)
+                .map(|bits| operator.eval(bits & 1 == 1, bits & 2 == 2))
+                .collect();
+            let actual = x.apply(&y, operator).unwrap().truth_table().unwrap();
+            assert_eq!(expected, actual, "{operator:?}");
+        }
+    }
+
+    #[test]
+    fn negate() {
+        let bdd: BinaryDecisionDiagram = "vars 1
+nodes 3
+0 1 2 0
+1 -1 -1 1
+2 -1 -1 0"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            vec![true, false],
+            bdd.negate().unwrap().truth_table().unwrap()
+        );
+    }
+
+    #[test]
+    fn constant() {
+        let bdd = BinaryDecisionDiagram::constant(true, 2);
+        assert_eq!(vec![true; 4], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn deep_chain() {
+        let bdd = and_chain(DEEP);
+        let negated = bdd.negate().unwrap();
+        let never = bdd.apply(&negated, BinaryOperator::And).unwrap();
+        assert_eq!(2, never.node_count());
+        assert_eq!(Ok(false), never.eval(&[true; DEEP]));
+        let same = bdd.apply(&bdd, BinaryOperator::Or).unwrap();
+        assert_eq!(DEEP + 2, same.node_count());
+    }
+}
@@ -0,0 +1,356 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::bdd::BinaryNode::{Decision, Terminal};
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator, SourceNode};
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+pub(crate) const FALSE_NODE: usize = 0;
+pub(crate) const TRUE_NODE: usize = 1;
+
+/// Hash-consing store used to build reduced ordered diagrams.
+///
+/// Node `0` is always the false terminal and node `1` the true terminal.
+/// Decision nodes created through [`DiagramBuilder::ite`] are ordered by
+/// ascending variable id.
+#[derive(Debug)]
+pub(crate) struct DiagramBuilder {
+    /// `(variable, low, high)` for each node; terminals test `usize::MAX`.
+    nodes: Vec<(usize, usize, usize)>,
+    /// Ids of collected nodes, reused before the store grows.
+    free: Vec<usize>,
+    unique: HashMap<(usize, usize, usize), usize>,
+    ite_cache: HashMap<(usize, usize, usize), usize>,
+}
+
+impl DiagramBuilder {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![
+                (usize::MAX, FALSE_NODE, FALSE_NODE),
+                (usize::MAX, TRUE_NODE, TRUE_NODE),
+            ],
+            free: Vec::new(),
+            unique: HashMap::new(),
+            ite_cache: HashMap::new(),
+        }
+    }
+
+    pub fn constant(value: bool) -> usize {
+        if value {
+            TRUE_NODE
+        } else {
+            FALSE_NODE
+        }
+    }
+
+    /// The `(variable, low, high)` triple of a decision node, or `None` for a
+    /// terminal.
+    pub fn decision(&self, id: usize) -> Option<(usize, usize, usize)> {
+        if id == FALSE_NODE || id == TRUE_NODE {
+            None
+        } else {
+            Some(self.nodes[id])
+        }
+    }
+
+    /// Find or create the node `(variable, low, high)`, skipping redundant
+    /// tests.
+    pub fn mk(&mut self, variable_id: usize, low: usize, high: usize) -> usize {
+        if low == high {
+            return low;
+        }
+        if let Some(&id) = self.unique.get(&(variable_id, low, high)) {
+            return id;
+        }
+        let id = if let Some(id) = self.free.pop() {
+            self.nodes[id] = (variable_id, low, high);
+            id
+        } else {
+            self.nodes.push((variable_id, low, high));
+            self.nodes.len() - 1
+        };
+        self.unique.insert((variable_id, low, high), id);
+        id
+    }
+
+    /// The number of nodes in use, including both terminals.
+    pub fn live_nodes(&self) -> usize { self.nodes.len() - self.free.len() }
+
+    /// Free every decision node not reachable from `roots`, returning how
+    /// many were freed. Freed ids are handed out again by later calls to
+    /// [`DiagramBuilder::mk`], so the computed cache is cleared.
+    pub fn collect(&mut self, roots: impl IntoIterator<Item = usize>) -> usize {
+        let mut marked = vec![false; self.nodes.len()];
+        let mut stack: Vec<usize> = roots.into_iter().collect();
+        while let Some(id) = stack.pop() {
+            if marked[id] {
+                continue;
+            }
+            marked[id] = true;
+            if let Some((_, low, high)) = self.decision(id) {
+                stack.push(low);
+                stack.push(high);
+            }
+        }
+
+        let mut freed = 0;
+        for (id, &is_marked) in marked.iter().enumerate().skip(TRUE_NODE + 1) {
+            let node = self.nodes[id];
+            if is_marked || node.0 == usize::MAX {
+                continue;
+            }
+            self.unique.remove(&node);
+            self.nodes[id] = (usize::MAX, FALSE_NODE, FALSE_NODE);
+            self.free.push(id);
+            freed += 1;
+        }
+        self.ite_cache.clear();
+        freed
+    }
+
+    pub fn variable(&mut self, variable_id: usize) -> usize {
+        self.mk(variable_id, FALSE_NODE, TRUE_NODE)
+    }
+
+    fn top_variable(&self, id: usize) -> usize { self.nodes[id].0 }
+
+    fn cofactors(&self, id: usize, variable_id: usize) -> (usize, usize) {
+        match self.decision(id) {
+            Some((node_variable, low, high)) if node_variable == variable_id => (low, high),
+            _ => (id, id),
+        }
+    }
+
+    /// If-then-else on ordered nodes of this builder, computed with an
+    /// explicit work stack so deep diagrams cannot overflow the call stack.
+    pub fn ite(&mut self, f: usize, g: usize, h: usize) -> usize {
+        /// A triple still to be computed, or one whose cofactors have been
+        /// and only need joining under `top`.
+        enum Task {
+            Expand(usize, usize, usize),
+            Join(usize, usize, usize, usize),
+        }
+        let mut tasks = vec![Task::Expand(f, g, h)];
+        let mut results = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Expand(f, g, h) => {
+                    if let Some(id) = self.ite_terminal(f, g, h) {
+                        results.push(id);
+                        continue;
+                    }
+                    let top = self
+                        .top_variable(f)
+                        .min(self.top_variable(g))
+                        .min(self.top_variable(h));
+                    let (f_low, f_high) = self.cofactors(f, top);
+                    let (g_low, g_high) = self.cofactors(g, top);
+                    let (h_low, h_high) = self.cofactors(h, top);
+                    tasks.push(Task::Join(f, g, h, top));
+                    tasks.push(Task::Expand(f_high, g_high, h_high));
+                    tasks.push(Task::Expand(f_low, g_low, h_low));
+                },
+                Task::Join(f, g, h, top) => {
+                    let high = results.pop().expect("high cofactor computed");
+                    let low = results.pop().expect("low cofactor computed");
+                    let id = self.mk(top, low, high);
+                    self.ite_cache.insert((f, g, h), id);
+                    results.push(id);
+                },
+            }
+        }
+        results.pop().expect("ite computed")
+    }
+
+    /// The result of an if-then-else that needs no recursion, if there is
+    /// one.
+    fn ite_terminal(&self, f: usize, g: usize, h: usize) -> Option<usize> {
+        if f == TRUE_NODE || g == h {
+            return Some(g);
+        }
+        if f == FALSE_NODE {
+            return Some(h);
+        }
+        if g == TRUE_NODE && h == FALSE_NODE {
+            return Some(f);
+        }
+        self.ite_cache.get(&(f, g, h)).copied()
+    }
+
+    pub fn not(&mut self, f: usize) -> usize { self.ite(f, FALSE_NODE, TRUE_NODE) }
+
+    pub fn apply(&mut self, operator: BinaryOperator, f: usize, g: usize) -> usize {
+        match operator {
+            BinaryOperator::And => self.ite(f, g, FALSE_NODE),
+            BinaryOperator::Or => self.ite(f, TRUE_NODE, g),
+            BinaryOperator::Xor => {
+                let not_g = self.not(g);
+                self.ite(f, not_g, g)
+            },
+            BinaryOperator::Nand => {
+                let and = self.ite(f, g, FALSE_NODE);
+                self.not(and)
+            },
+            BinaryOperator::Nor => {
+                let or = self.ite(f, TRUE_NODE, g);
+                self.not(or)
+            },
+            BinaryOperator::Xnor => {
+                let not_g = self.not(g);
+                self.ite(f, g, not_g)
+            },
+            BinaryOperator::Implies => self.ite(f, g, TRUE_NODE),
+        }
+    }
+
+    /// Rebuild the function of `bdd` inside this builder in ascending
+    /// variable order, returning the id of its root.
+    ///
+    /// Any well-formed diagram is accepted, including free diagrams whose
+    /// paths test variables in different orders.
+    /// # Errors
+    /// * `EvaluationError` - a referenced node is missing, the diagram contains
+    ///   a cycle, or a node tests variable `usize::MAX`
+    pub fn import(&mut self, bdd: &BinaryDecisionDiagram) -> Result<usize, FlowError> {
+        if bdd.nodes.get(bdd.entry_node.0).is_none() {
+            return Err(EvaluationError("Unable to grab entry node"));
+        }
+        let mut imported = vec![FALSE_NODE; bdd.nodes.len()];
+        for index in bdd.topological_order()? {
+            imported[index.0] = match &bdd.nodes[index.0] {
+                Terminal(value) => Self::constant(*value),
+                // Terminals are stored as tests of `usize::MAX`.
+                Decision(node) if node.variable_id == usize::MAX => {
+                    return Err(EvaluationError("Variable id is out of range"));
+                },
+                Decision(node) => {
+                    let (low, high) = node.decision_map;
+                    let variable = self.variable(node.variable_id);
+                    self.ite(variable, imported[high.0], imported[low.0])
+                },
+            };
+        }
+        Ok(imported[bdd.entry_node.0])
+    }
+
+    /// Extract the nodes reachable from `root` into a standalone diagram,
+    /// keeping the builder ids as node ids. Both terminals are always kept so
+    /// the result can be written out.
+    pub fn build(&self, root: usize, variables: usize) -> BinaryDecisionDiagram {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut nodes = Vec::new();
+        let mut stack = vec![root, FALSE_NODE, TRUE_NODE];
+        let mut variables = variables;
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            let node = match self.decision(id) {
+                None => SourceNode::Terminal(id == TRUE_NODE),
+                Some((variable_id, low, high)) => {
+                    variables = variables.max(variable_id + 1);
+                    stack.push(low);
+                    stack.push(high);
+                    SourceNode::Decision(variable_id, low, high)
+                },
+            };
+            nodes.push((id, node));
+        }
+
+        BinaryDecisionDiagram::arrange(variables, nodes, root)
+            .expect("builder nodes only refer to other builder nodes")
+    }
+}
+
+/// Levels in the diagrams that check deep traversals, well past where a
+/// recursive traversal overflows a test thread's stack.
+#[cfg(test)]
+pub(crate) const DEEP: usize = 20_000;
+
+/// The conjunction of `variables` variables, a diagram one node per level
+/// deep.
+#[cfg(test)]
+pub(crate) fn and_chain(variables: usize) -> BinaryDecisionDiagram {
+    let mut builder = DiagramBuilder::new();
+    let root = (0..variables).rev().fold(TRUE_NODE, |node, variable_id| {
+        builder.mk(variable_id, FALSE_NODE, node)
+    });
+    builder.build(root, variables)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{DiagramBuilder, FALSE_NODE, TRUE_NODE};
+    use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+    use crate::Evaluate;
+
+    #[test]
+    fn mk_shares_nodes() {
+        let mut builder = DiagramBuilder::new();
+        let first = builder.mk(0, FALSE_NODE, TRUE_NODE);
+        let second = builder.mk(0, FALSE_NODE, TRUE_NODE);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn mk_skips_redundant_test() {
+        let mut builder = DiagramBuilder::new();
+        assert_eq!(TRUE_NODE, builder.mk(0, TRUE_NODE, TRUE_NODE));
+    }
+
+    #[test]
+    fn excluded_middle() {
+        let mut builder = DiagramBuilder::new();
+        let x = builder.variable(0);
+        let not_x = builder.not(x);
+        assert_eq!(TRUE_NODE, builder.apply(BinaryOperator::Or, x, not_x));
+        assert_eq!(FALSE_NODE, builder.apply(BinaryOperator::And, x, not_x));
+    }
+
+    #[test]
+    fn collect_reuses_ids() {
+        let mut builder = DiagramBuilder::new();
+        let x = builder.variable(0);
+        let y = builder.variable(1);
+        let and = builder.apply(BinaryOperator::And, x, y);
+        assert_eq!(1, builder.collect([and]));
+        assert_eq!(4, builder.live_nodes());
+        assert_eq!(Some((1, FALSE_NODE, TRUE_NODE)), builder.decision(y));
+        assert_eq!(x, builder.variable(2));
+    }
+
+    #[test]
+    fn import_free_diagram() {
+        let bdd: BinaryDecisionDiagram = "vars 2
+nodes 5
+0 1 2 1
+1 4 3 0
+2 3 4 0
+3 -1 -1 0
+4 -1 -1 1"
+            .parse()
+            .unwrap();
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(&bdd).unwrap();
+        let rebuilt = builder.build(root, bdd.variables);
+        assert_eq!(bdd.truth_table(), rebuilt.truth_table());
+    }
+}
@@ -0,0 +1,486 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::ops::Not;
+use std::str::FromStr;
+
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE, TRUE_NODE};
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{convert_bits_to_bools, Evaluate, FlowError};
+
+/// An edge is a node id shifted left once, with the low bit set when the
+/// function below it is negated. Node `0` is the only terminal, true.
+type Edge = usize;
+
+const TRUE_EDGE: Edge = 0;
+const FALSE_EDGE: Edge = 1;
+
+/// A reduced ordered diagram with complement edges. Negation only flips a
+/// bit on the root edge, so a function and its complement share every node,
+/// and there is a single terminal.
+///
+/// The diagram reads and writes the same two-terminal text format as
+/// [`BinaryDecisionDiagram`].
+#[derive(Clone, Debug)]
+pub struct ComplementedDiagram {
+    variables: usize,
+    nodes: Vec<(usize, Edge, Edge)>,
+    unique: HashMap<(usize, Edge, Edge), usize>,
+    root: Edge,
+}
+
+impl ComplementedDiagram {
+    fn with_variables(variables: usize) -> Self {
+        Self {
+            variables,
+            nodes: vec![(usize::MAX, TRUE_EDGE, TRUE_EDGE)],
+            unique: HashMap::new(),
+            root: TRUE_EDGE,
+        }
+    }
+
+    /// A diagram over `variables` variables that always evaluates to `value`.
+    #[must_use]
+    pub fn constant(value: bool, variables: usize) -> Self {
+        let mut diagram = Self::with_variables(variables);
+        diagram.root = if value { TRUE_EDGE } else { FALSE_EDGE };
+        diagram
+    }
+
+    /// A diagram over `variables` variables that evaluates to the value of
+    /// `variable_id`.
+    #[must_use]
+    pub fn variable(variable_id: usize, variables: usize) -> Self {
+        let mut diagram = Self::with_variables(variables.max(variable_id + 1));
+        diagram.root = diagram.mk(variable_id, FALSE_EDGE, TRUE_EDGE);
+        diagram
+    }
+
+    /// The number of nodes reachable from the root, counting the terminal.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root >> 1];
+        let mut count = 0;
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            count += 1;
+            if id != 0 {
+                let (_, low, high) = self.nodes[id];
+                stack.push(low >> 1);
+                stack.push(high >> 1);
+            }
+        }
+        count
+    }
+
+    /// Combine two diagrams with a boolean operator.
+    #[must_use]
+    pub fn apply(&self, other: &Self, operator: BinaryOperator) -> Self {
+        let mut diagram = self.clone();
+        diagram.variables = self.variables.max(other.variables);
+        let right = diagram.import(other, other.root, &mut HashMap::new());
+        let mut cache = HashMap::new();
+        let (f, g) = (self.root, right);
+        let root = match operator {
+            BinaryOperator::And => diagram.ite(f, g, FALSE_EDGE, &mut cache),
+            BinaryOperator::Or => diagram.ite(f, TRUE_EDGE, g, &mut cache),
+            BinaryOperator::Xor => diagram.ite(f, g ^ 1, g, &mut cache),
+            BinaryOperator::Nand => diagram.ite(f, g, FALSE_EDGE, &mut cache) ^ 1,
+            BinaryOperator::Nor => diagram.ite(f, TRUE_EDGE, g, &mut cache) ^ 1,
+            BinaryOperator::Xnor => diagram.ite(f, g, g ^ 1, &mut cache),
+            BinaryOperator::Implies => diagram.ite(f, g, TRUE_EDGE, &mut cache),
+        };
+        diagram.rooted_at(root)
+    }
+
+    /// Find or create the node for `(variable, low, high)`. The high edge of a
+    /// stored node is never complemented, which keeps the form canonical.
+    fn mk(&mut self, variable_id: usize, low: Edge, high: Edge) -> Edge {
+        if low == high {
+            return low;
+        }
+        if high & 1 == 1 {
+            return self.mk(variable_id, low ^ 1, high ^ 1) ^ 1;
+        }
+        if let Some(&id) = self.unique.get(&(variable_id, low, high)) {
+            return id << 1;
+        }
+        let id = self.nodes.len();
+        self.nodes.push((variable_id, low, high));
+        self.unique.insert((variable_id, low, high), id);
+        id << 1
+    }
+
+    fn top_variable(&self, edge: Edge) -> usize { self.nodes[edge >> 1].0 }
+
+    fn cofactors(&self, edge: Edge, variable_id: usize) -> (Edge, Edge) {
+        let (node_variable, low, high) = self.nodes[edge >> 1];
+        if edge >> 1 != 0 && node_variable == variable_id {
+            (low ^ (edge & 1), high ^ (edge & 1))
+        } else {
+            (edge, edge)
+        }
+    }
+
+    fn ite(
+        &mut self,
+        f: Edge,
+        g: Edge,
+        h: Edge,
+        cache: &mut HashMap<(Edge, Edge, Edge), Edge>,
+    ) -> Edge {
+        /// A triple still to be computed, or one whose cofactors have been
+        /// and only need joining under `top`.
+        enum Task {
+            Expand(Edge, Edge, Edge),
+            Join(Edge, Edge, Edge, usize),
+        }
+        let mut tasks = vec![Task::Expand(f, g, h)];
+        let mut results = Vec::new();
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Expand(f, g, h) => {
+                    if let Some(edge) = ite_terminal(f, g, h, cache) {
+                        results.push(edge);
+                        continue;
+                    }
+                    let top = self
+                        .top_variable(f)
+                        .min(self.top_variable(g))
+                        .min(self.top_variable(h));
+                    let (f_low, f_high) = self.cofactors(f, top);
+                    let (g_low, g_high) = self.cofactors(g, top);
+                    let (h_low, h_high) = self.cofactors(h, top);
+                    tasks.push(Task::Join(f, g, h, top));
+                    tasks.push(Task::Expand(f_high, g_high, h_high));
+                    tasks.push(Task::Expand(f_low, g_low, h_low));
+                },
+                Task::Join(f, g, h, top) => {
+                    let high = results.pop().expect("high cofactor computed");
+                    let low = results.pop().expect("low cofactor computed");
+                    let edge = self.mk(top, low, high);
+                    cache.insert((f, g, h), edge);
+                    results.push(edge);
+                },
+            }
+        }
+        results.pop().expect("ite computed")
+    }
+
+    /// Copy the nodes of `other` below `edge` into this diagram.
+    fn import(&mut self, other: &Self, edge: Edge, imported: &mut HashMap<usize, Edge>) -> Edge {
+        imported.insert(0, TRUE_EDGE);
+        let mut stack = vec![(edge >> 1, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if imported.contains_key(&id) {
+                continue;
+            }
+            let (variable_id, low, high) = other.nodes[id];
+            if expanded {
+                let low = imported[&(low >> 1)] ^ (low & 1);
+                let high = imported[&(high >> 1)] ^ (high & 1);
+                let new_edge = self.mk(variable_id, low, high);
+                imported.insert(id, new_edge);
+            } else {
+                stack.push((id, true));
+                stack.push((high >> 1, false));
+                stack.push((low >> 1, false));
+            }
+        }
+        imported[&(edge >> 1)] ^ (edge & 1)
+    }
+
+    /// A copy of this diagram rooted at `root`, without unreachable nodes.
+    fn rooted_at(&self, root: Edge) -> Self {
+        let mut diagram = Self::with_variables(self.variables);
+        diagram.root = diagram.import(self, root, &mut HashMap::new());
+        diagram
+    }
+}
+
+impl Not for ComplementedDiagram {
+    type Output = Self;
+
+    /// Negate the diagram by flipping the root edge.
+    fn not(mut self) -> Self {
+        self.root ^= 1;
+        self
+    }
+}
+
+impl TryFrom<&BinaryDecisionDiagram> for ComplementedDiagram {
+    type Error = FlowError;
+
+    fn try_from(bdd: &BinaryDecisionDiagram) -> Result<Self, Self::Error> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(bdd)?;
+        let variables = builder.build(root, bdd.variables).variables;
+
+        let mut diagram = Self::with_variables(variables);
+        let mut converted: HashMap<usize, Edge> = HashMap::new();
+        diagram.root = diagram.import_builder(&builder, root, &mut converted);
+        Ok(diagram)
+    }
+}
+
+impl ComplementedDiagram {
+    fn import_builder(
+        &mut self,
+        builder: &DiagramBuilder,
+        root: usize,
+        converted: &mut HashMap<usize, Edge>,
+    ) -> Edge {
+        let mut stack = vec![(root, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if converted.contains_key(&id) {
+                continue;
+            }
+            match builder.decision(id) {
+                None => {
+                    let edge = if id == TRUE_NODE {
+                        TRUE_EDGE
+                    } else {
+                        FALSE_EDGE
+                    };
+                    converted.insert(id, edge);
+                },
+                Some((_, low, high)) if !expanded => {
+                    stack.push((id, true));
+                    stack.push((high, false));
+                    stack.push((low, false));
+                },
+                Some((variable_id, low, high)) => {
+                    let edge = self.mk(variable_id, converted[&low], converted[&high]);
+                    converted.insert(id, edge);
+                },
+            }
+        }
+        converted[&root]
+    }
+
+    fn export_builder(
+        &self,
+        builder: &mut DiagramBuilder,
+        root: Edge,
+        converted: &mut HashMap<Edge, usize>,
+    ) -> usize {
+        converted.insert(TRUE_EDGE, TRUE_NODE);
+        converted.insert(FALSE_EDGE, FALSE_NODE);
+        let mut stack = vec![(root, false)];
+        while let Some((edge, expanded)) = stack.pop() {
+            if converted.contains_key(&edge) {
+                continue;
+            }
+            let (variable_id, low, high) = self.nodes[edge >> 1];
+            let (low, high) = (low ^ (edge & 1), high ^ (edge & 1));
+            if expanded {
+                let id = builder.mk(variable_id, converted[&low], converted[&high]);
+                converted.insert(edge, id);
+            } else {
+                stack.push((edge, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            }
+        }
+        converted[&root]
+    }
+}
+
+/// The result of an if-then-else that needs no recursion, if there is one.
+fn ite_terminal(
+    f: Edge,
+    g: Edge,
+    h: Edge,
+    cache: &HashMap<(Edge, Edge, Edge), Edge>,
+) -> Option<Edge> {
+    if f == TRUE_EDGE || g == h {
+        return Some(g);
+    }
+    if f == FALSE_EDGE {
+        return Some(h);
+    }
+    if g == TRUE_EDGE && h == FALSE_EDGE {
+        return Some(f);
+    }
+    if g == FALSE_EDGE && h == TRUE_EDGE {
+        return Some(f ^ 1);
+    }
+    cache.get(&(f, g, h)).copied()
+}
+
+impl From<&ComplementedDiagram> for BinaryDecisionDiagram {
+    /// Expand the complement edges into a diagram with two terminals.
+    fn from(diagram: &ComplementedDiagram) -> Self {
+        let mut builder = DiagramBuilder::new();
+        let root = diagram.export_builder(&mut builder, diagram.root, &mut HashMap::new());
+        builder.build(root, diagram.variables)
+    }
+}
+
+impl FromStr for ComplementedDiagram {
+    type Err = FlowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bdd: BinaryDecisionDiagram = s.parse()?;
+        Self::try_from(&bdd)
+    }
+}
+
+impl Display for ComplementedDiagram {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", BinaryDecisionDiagram::from(self))
+    }
+}
+
+impl Evaluate for ComplementedDiagram {
+    fn eval(&self, values: &[bool]) -> Result<bool, FlowError> {
+        if values.len() < self.variables {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let mut edge = self.root;
+        while edge >> 1 != 0 {
+            let (variable_id, low, high) = self.nodes[edge >> 1];
+            let next = if values[variable_id] { high } else { low };
+            edge = next ^ (edge & 1);
+        }
+        Ok(edge == TRUE_EDGE)
+    }
+
+    fn truth_table(&self) -> Result<Vec<bool>, FlowError> {
+        let combinations = u32::try_from(self.variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
+        let mut results: Vec<bool> = Vec::new();
+
+        for var_set in 0..combinations {
+            let vars = convert_bits_to_bools(var_set, self.variables);
+            results.push(self.eval(&vars)?);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::{BinaryDecisionDiagram, BinaryOperator, ComplementedDiagram};
+    use crate::Evaluate;
+
+    fn parity(variables: usize) -> ComplementedDiagram {
+        (1..variables).fold(
+            ComplementedDiagram::variable(0, variables),
+            |acc, variable| {
+                acc.apply(
+                    &ComplementedDiagram::variable(variable, variables),
+                    BinaryOperator::Xor,
+                )
+            },
+        )
+    }
+
+    #[test]
+    fn negation_shares_nodes() {
+        let diagram = parity(3);
+        let count = diagram.node_count();
+        let negated = !diagram.clone();
+        assert_eq!(count, negated.node_count());
+        let expected: Vec<bool> = diagram.truth_table().unwrap().iter().map(|b| !b).collect();
+        assert_eq!(expected, negated.truth_table().unwrap());
+    }
+
+    #[test]
+    fn xor_is_smaller() {
+        let diagram = parity(4);
+        let table: Vec<bool> = (0..16usize).map(|i| i.count_ones() % 2 == 1).collect();
+        assert_eq!(table, diagram.truth_table().unwrap());
+        // One node per variable plus the single terminal, against two per
+        // variable and two terminals without complement edges.
+        assert_eq!(5, diagram.node_count());
+        let bdd = BinaryDecisionDiagram::from(&diagram);
+        assert_eq!(9, bdd.nodes.len());
+    }
+
+    #[test]
+    fn apply_matches_pointwise() {
+        let x = ComplementedDiagram::variable(0, 2);
+        let y = !ComplementedDiagram::variable(1, 2);
+        for operator in [
+            BinaryOperator::And,
+            BinaryOperator::Or,
+            BinaryOperator::Xor,
+            BinaryOperator::Nand,
+            BinaryOperator::Nor,
+            BinaryOperator::Xnor,
+            BinaryOperator::Implies,
+        ] {
+            let expected: Vec<bool> = (0..4)
+                .map(|bits| operator.eval(bits & 1 == 1, bits & 2 == 0))
+                .collect();
+            assert_eq!(
+                expected,
+                x.apply(&y, operator).truth_table().unwrap(),
+                "{operator:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn text_format_round_trip() {
+        const NOT_BDD: &str = "vars 1
+nodes 3
+0 2 1 0
+1 -1 -1 1
+2 -1 -1 0";
+        let diagram: ComplementedDiagram = NOT_BDD.parse().unwrap();
+        assert_eq!(vec![true, false], diagram.truth_table().unwrap());
+        let bdd = BinaryDecisionDiagram::from(&diagram);
+        assert_eq!(vec![true, false], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn constants() {
+        assert_eq!(
+            vec![false; 2],
+            ComplementedDiagram::constant(false, 1)
+                .truth_table()
+                .unwrap()
+        );
+        assert_eq!(1, ComplementedDiagram::constant(true, 1).node_count());
+    }
+
+    #[test]
+    fn deep_chain() {
+        let bdd = and_chain(DEEP);
+        let diagram = ComplementedDiagram::try_from(&bdd).unwrap();
+        assert_eq!(DEEP + 1, diagram.node_count());
+        let negated = !diagram.clone();
+        assert_eq!(1, diagram.apply(&negated, BinaryOperator::Or).node_count());
+        assert_eq!(Ok(true), diagram.eval(&vec![true; DEEP]));
+        let round_trip = BinaryDecisionDiagram::from(&diagram.apply(&diagram, BinaryOperator::And));
+        assert_eq!(bdd.nodes.len(), round_trip.nodes.len());
+    }
+}
@@ -0,0 +1,211 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::bdd::BinaryDecisionDiagram;
+use crate::bdd::BinaryNode::{Decision, Terminal};
+use crate::crossbar::{lay_out, CrossbarMatrix, Literal};
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+/// Net of the true terminal, where current enters.
+const TRUE: usize = 0;
+/// Net of the false terminal, which nothing is connected to.
+const FALSE: usize = 1;
+
+impl BinaryDecisionDiagram {
+    /// Synthesize diagrams onto one crossbar with an output wire per diagram,
+    /// in order.
+    ///
+    /// Every decision node becomes a net, joined to the child it takes when
+    /// its variable is false by a negative literal and to the other child by
+    /// a positive one. Current enters at the true terminal, so an output is
+    /// reached exactly when its diagram's path ends there: under any
+    /// assignment each node conducts to one child at most, so no sneak path
+    /// can join an output to the input. Nodes that test the same variable
+    /// and lead to the same nets are shared across diagrams, and nodes whose
+    /// children are the same net are skipped. The nets are then laid out
+    /// like [`CrossbarMatrix::minimize`] lays them out.
+    /// # Errors
+    /// * `EvaluationError` - there are no diagrams, or a diagram references a
+    ///   missing node
+    /// # Example
+    /// ```
+    /// use flow::bdd::{BddManager, BinaryDecisionDiagram, BinaryOperator};
+    /// let manager = BddManager::new(2);
+    /// let (x0, x1) = (manager.variable(0), manager.variable(1));
+    /// let sum = x0.apply(&x1, BinaryOperator::Xor).unwrap().to_diagram();
+    /// let carry = x0.apply(&x1, BinaryOperator::And).unwrap().to_diagram();
+    /// let crossbar = BinaryDecisionDiagram::to_crossbar(&[sum, carry]).unwrap();
+    /// assert_eq!(Ok(vec![true, false]), crossbar.eval_outputs(&[true, false]));
+    /// assert_eq!(Ok(vec![false, true]), crossbar.eval_outputs(&[true, true]));
+    /// ```
+    pub fn to_crossbar(diagrams: &[Self]) -> Result<CrossbarMatrix, FlowError> {
+        if diagrams.is_empty() {
+            return Err(EvaluationError("There are no diagrams to synthesize"));
+        }
+        let mut unique: HashMap<(usize, usize, usize), usize> = HashMap::new();
+        let mut devices = Vec::new();
+        let mut outputs = Vec::with_capacity(diagrams.len());
+        for bdd in diagrams {
+            let mut nets = HashMap::new();
+            for index in bdd.topological_order()? {
+                let net = match &bdd.nodes[index.0] {
+                    Terminal(true) => TRUE,
+                    Terminal(false) => FALSE,
+                    Decision(node) => {
+                        let (low, high) = (nets[&node.decision_map.0], nets[&node.decision_map.1]);
+                        if low == high {
+                            low
+                        } else {
+                            let next = unique.len() + 2;
+                            *unique
+                                .entry((node.variable_id, low, high))
+                                .or_insert_with(|| {
+                                    for (child, literal) in [
+                                        (low, Literal::Negative(node.variable_id)),
+                                        (high, Literal::Positive(node.variable_id)),
+                                    ] {
+                                        if child != FALSE {
+                                            devices.push((next, child, literal));
+                                        }
+                                    }
+                                    next
+                                })
+                        }
+                    },
+                };
+                nets.insert(index, net);
+            }
+            outputs.push(nets[&bdd.entry_node]);
+        }
+        lay_out(unique.len() + 2, devices, TRUE, &outputs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::{BddManager, BinaryDecisionDiagram, BinaryOperator};
+    use crate::crossbar::CrossbarMatrix;
+    use crate::gen::{random_bdd, BddOptions};
+    use crate::{convert_bits_to_bools, Evaluate};
+
+    /// Every output of the crossbar agrees with its diagram everywhere.
+    fn check(diagrams: &[BinaryDecisionDiagram], crossbar: &CrossbarMatrix, variables: usize) {
+        for var_set in 0..1 << variables {
+            let values = convert_bits_to_bools(var_set, variables);
+            let expected: Vec<bool> = diagrams
+                .iter()
+                .map(|bdd| bdd.eval(&values).unwrap())
+                .collect();
+            assert_eq!(Ok(expected), crossbar.eval_outputs(&values), "{values:?}");
+        }
+    }
+
+    /// The sum and carry-out bits of a two-bit adder of x0 x1 and x2 x3.
+    fn adder() -> Vec<BinaryDecisionDiagram> {
+        let manager = BddManager::new(4);
+        let x: Vec<_> = (0..4).map(|id| manager.variable(id)).collect();
+        let sum0 = x[0].apply(&x[2], BinaryOperator::Xor).unwrap();
+        let carry0 = x[0].apply(&x[2], BinaryOperator::And).unwrap();
+        let half = x[1].apply(&x[3], BinaryOperator::Xor).unwrap();
+        let sum1 = half.apply(&carry0, BinaryOperator::Xor).unwrap();
+        let generate = x[1].apply(&x[3], BinaryOperator::And).unwrap();
+        let propagate = half.apply(&carry0, BinaryOperator::And).unwrap();
+        let carry1 = generate.apply(&propagate, BinaryOperator::Or).unwrap();
+        [sum0, sum1, carry1]
+            .iter()
+            .map(|bdd| bdd.to_diagram())
+            .collect()
+    }
+
+    #[test]
+    fn single_output() {
+        let bdd: BinaryDecisionDiagram = "vars 2
+nodes 4
+root 0
+0 2 1 0
+1 2 3 1
+2 -1 -1 0
+3 -1 -1 1"
+            .parse()
+            .unwrap();
+        let crossbar = BinaryDecisionDiagram::to_crossbar(std::slice::from_ref(&bdd)).unwrap();
+        assert_eq!(bdd.truth_table(), crossbar.truth_table());
+        assert_eq!(1, crossbar.outputs().len());
+    }
+
+    #[test]
+    fn multiple_outputs() {
+        let diagrams = adder();
+        let shared = BinaryDecisionDiagram::to_crossbar(&diagrams).unwrap();
+        assert_eq!(3, shared.outputs().len());
+        check(&diagrams, &shared, 4);
+
+        // Separate crossbars side by side along the diagonal of one.
+        let (rows, columns) = diagrams
+            .iter()
+            .map(|bdd| BinaryDecisionDiagram::to_crossbar(std::slice::from_ref(bdd)).unwrap())
+            .fold((0, 0), |(rows, columns), crossbar| {
+                (rows + crossbar.rows(), columns + crossbar.columns())
+            });
+        assert!(shared.rows() + shared.columns() < rows + columns);
+        assert!(shared.area() < rows * columns);
+    }
+
+    #[test]
+    fn constants() {
+        let manager = BddManager::new(1);
+        let diagrams = [
+            manager.constant(true).to_diagram(),
+            manager.constant(false).to_diagram(),
+            manager.variable(0).to_diagram(),
+        ];
+        let crossbar = BinaryDecisionDiagram::to_crossbar(&diagrams).unwrap();
+        check(&diagrams, &crossbar, 1);
+        assert!(BinaryDecisionDiagram::to_crossbar(&[]).is_err());
+    }
+
+    #[test]
+    fn random() {
+        for seed in 0..20 {
+            let diagrams: Vec<_> = (0..3)
+                .map(|offset| {
+                    random_bdd(&BddOptions {
+                        variables: 5,
+                        nodes: 12,
+                        seed: seed * 3 + offset,
+                        ..BddOptions::default()
+                    })
+                    .unwrap()
+                })
+                .collect();
+            let crossbar = BinaryDecisionDiagram::to_crossbar(&diagrams).unwrap();
+            check(&diagrams, &crossbar, 5);
+        }
+    }
+
+    #[test]
+    fn minimize_after_synthesis() {
+        let diagrams = adder();
+        let synthesized = BinaryDecisionDiagram::to_crossbar(&diagrams).unwrap();
+        let minimized = synthesized.minimize().unwrap();
+        assert!(minimized.area() <= synthesized.area());
+        assert!(synthesized.equivalent(&minimized).unwrap());
+        check(&diagrams, &minimized, 4);
+    }
+}
@@ -0,0 +1,160 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE, TRUE_NODE};
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+impl BinaryDecisionDiagram {
+    /// Read a formula in DIMACS CNF and conjoin its clauses into a diagram.
+    ///
+    /// DIMACS variable `n` becomes variable id `n - 1`. Comment lines (`c`)
+    /// are skipped and a `%` line ends the clause list.
+    /// # Errors
+    /// * `ParseError` - the problem line is missing or malformed, a literal is
+    ///   out of range, or the number of clauses does not match the header
+    /// # Example
+    /// ```
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// const CNF: &str = "c x1 or not x2
+    /// p cnf 2 1
+    /// 1 -2 0";
+    /// let bdd = BinaryDecisionDiagram::from_dimacs(CNF).unwrap();
+    /// assert_eq!(3, bdd.model_count().unwrap());
+    /// ```
+    pub fn from_dimacs(s: &str) -> Result<Self, FlowError> {
+        let mut header: Option<(usize, usize)> = None;
+        let mut clauses: Vec<Vec<isize>> = Vec::new();
+        let mut clause: Vec<isize> = Vec::new();
+
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('c') {
+                continue;
+            }
+            if line.starts_with('%') {
+                break;
+            }
+            if line.starts_with('p') {
+                if header.is_some() {
+                    return Err(ParseError("Problem line appears more than once"));
+                }
+                let mut split = line.split_ascii_whitespace().skip(1);
+                if split.next() != Some("cnf") {
+                    return Err(ParseError("Problem line is not of format cnf"));
+                }
+                let num_vars = split
+                    .next()
+                    .ok_or(ParseError("Problem line does not specify variables"))?
+                    .parse::<usize>()?;
+                let num_clauses = split
+                    .next()
+                    .ok_or(ParseError("Problem line does not specify clauses"))?
+                    .parse::<usize>()?;
+                header = Some((num_vars, num_clauses));
+                continue;
+            }
+
+            let (num_vars, _) = header.ok_or(ParseError("Clause before problem line"))?;
+            for literal in line.split_ascii_whitespace() {
+                let literal = literal.parse::<isize>()?;
+                if literal == 0 {
+                    clauses.push(std::mem::take(&mut clause));
+                    continue;
+                }
+                if literal.unsigned_abs() > num_vars {
+                    return Err(ParseError("Literal refers to an undeclared variable"));
+                }
+                clause.push(literal);
+            }
+        }
+        if !clause.is_empty() {
+            clauses.push(clause);
+        }
+
+        let (num_vars, num_clauses) = header.ok_or(ParseError("Problem line not present"))?;
+        if clauses.len() != num_clauses {
+            return Err(ParseError("Number of clauses does not match problem line"));
+        }
+
+        let mut builder = DiagramBuilder::new();
+        let mut root = TRUE_NODE;
+        for clause in &clauses {
+            let mut disjunction = FALSE_NODE;
+            for &literal in clause {
+                let mut variable = builder.variable(literal.unsigned_abs() - 1);
+                if literal < 0 {
+                    variable = builder.not(variable);
+                }
+                disjunction = builder.apply(BinaryOperator::Or, disjunction, variable);
+            }
+            root = builder.apply(BinaryOperator::And, root, disjunction);
+        }
+
+        Ok(builder.build(root, num_vars))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::Evaluate;
+
+    const XOR_CNF: &str = "c x1 xor x2
+p cnf 2 2
+1 2 0
+-1 -2 0";
+
+    #[test]
+    fn from_dimacs() {
+        let bdd = BinaryDecisionDiagram::from_dimacs(XOR_CNF).unwrap();
+        assert_eq!(2, bdd.variables);
+        assert_eq!(vec![false, true, true, false], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn clause_across_lines() {
+        let bdd = BinaryDecisionDiagram::from_dimacs(
+            "p cnf 3 1
+1 2
+3 0",
+        )
+        .unwrap();
+        assert_eq!(Ok(false), bdd.eval(&[false, false, false]));
+        assert_eq!(Ok(true), bdd.eval(&[false, false, true]));
+    }
+
+    #[test]
+    fn empty_formula() {
+        let bdd = BinaryDecisionDiagram::from_dimacs("p cnf 2 0").unwrap();
+        assert_eq!(vec![true; 4], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn missing_problem_line() {
+        assert!(BinaryDecisionDiagram::from_dimacs("1 2 0").is_err());
+    }
+
+    #[test]
+    fn undeclared_variable() {
+        assert!(BinaryDecisionDiagram::from_dimacs("p cnf 1 1\n1 2 0").is_err());
+    }
+
+    #[test]
+    fn clause_count_mismatch() {
+        assert!(BinaryDecisionDiagram::from_dimacs("p cnf 2 2\n1 2 0").is_err());
+    }
+}
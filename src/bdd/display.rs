@@ -20,11 +20,21 @@ use crate::bdd::{BinaryDecisionDiagram, BinaryNode, DecisionNode};
 
 impl Display for BinaryDecisionDiagram {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let num_nodes = self.nodes.keys().len();
+        let num_nodes = self.nodes.len();
         writeln!(f, "vars {}", self.variables)?;
-        write!(f, "nodes {num_nodes}")?;
-        for mapping in &self.nodes {
-            write!(f, "\n{} {}", mapping.0, mapping.1)?;
+        writeln!(f, "nodes {num_nodes}")?;
+        write!(f, "root {}", self.node_ids[self.entry_node.0])?;
+        for (node, id) in self.nodes.iter().zip(&self.node_ids) {
+            match node {
+                BinaryNode::Decision(decision) => write!(
+                    f,
+                    "\n{id} {} {} {}",
+                    self.node_ids[decision.decision_map.1 .0],
+                    self.node_ids[decision.decision_map.0 .0],
+                    decision.variable_id
+                )?,
+                BinaryNode::Terminal(_) => write!(f, "\n{id} {node}")?,
+            }
         }
         write!(f, "")
     }
@@ -45,20 +55,20 @@ impl Display for DecisionNode {
         write!(
             f,
             "{} {} {}",
-            self.decision_map.1, self.decision_map.0, self.variable_id
+            self.decision_map.1 .0, self.decision_map.0 .0, self.variable_id
         )
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::bdd::{BinaryDecisionDiagram, BinaryNode, DecisionNode};
+    use crate::bdd::{BinaryDecisionDiagram, BinaryNode, DecisionNode, NodeIndex};
 
     #[test]
     fn decision_node() {
         let node = DecisionNode {
             variable_id: 3,
-            decision_map: (2, 1),
+            decision_map: (NodeIndex(2), NodeIndex(1)),
         };
         assert_eq!(format!("{node}"), "1 2 3");
     }
@@ -67,7 +77,7 @@ mod test {
     fn binary_node_decision() {
         let node = DecisionNode {
             variable_id: 3,
-            decision_map: (2, 1),
+            decision_map: (NodeIndex(2), NodeIndex(1)),
         };
         let binary_node = BinaryNode::Decision(node);
         assert_eq!(format!("{binary_node}"), "1 2 3");
@@ -87,6 +97,7 @@ mod test {
 
     const SIMPLE_BDD: &str = "vars 1
 nodes 3
+root 0
 0 1 2 0
 1 -1 -1 0
 2 -1 -1 1";
@@ -28,18 +28,18 @@ impl Evaluate for BinaryDecisionDiagram {
         }
         let mut cur_node = self
             .nodes
-            .get(&self.entry_node)
+            .get(self.entry_node.0)
             .ok_or(EvaluationError("Unable to grab entry node"))?;
 
         loop {
             match cur_node {
                 Decision(decision_node) => {
-                    let var = values[decision_node.variable_id];
-                    let next_node = decision_node.evaluate(var);
-                    cur_node = self
-                        .nodes
-                        .get(&next_node)
-                        .ok_or(EvaluationError("Could not traverse to next node"))?;
+                    let var = *values
+                        .get(decision_node.variable_id)
+                        .ok_or(EvaluationError(
+                            "A node tests a variable beyond the end of the assignment",
+                        ))?;
+                    cur_node = &self.nodes[decision_node.evaluate(var).0];
                 },
                 Terminal(b) => return Ok(*b),
             }
@@ -47,10 +47,10 @@ impl Evaluate for BinaryDecisionDiagram {
     }
 
     fn truth_table(&self) -> Result<Vec<bool>, FlowError> {
-        if self.variables > usize::BITS as usize {
-            return Err(EvaluationError("Too many variables"));
-        }
-        let combinations: usize = 1 << self.variables;
+        let combinations = u32::try_from(self.variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
         let mut results: Vec<bool> = Vec::new();
 
         for var_set in 0..combinations {
@@ -68,6 +68,7 @@ mod test {
 
     use crate::bdd::BinaryDecisionDiagram;
     use crate::Evaluate;
+    use crate::FlowError::ParseError;
 
     const SIMPLE_BDD: &str = "vars 1
 nodes 3
@@ -82,6 +83,21 @@ nodes 3
         assert!(!bdd.eval(&bools).expect("Could not evaluate"));
     }
 
+    #[test]
+    fn variable_outside_assignment() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 2 1 3
+1 -1 -1 0
+2 -1 -1 1",
+        );
+        assert_eq!(
+            Some(ParseError("Var ID is not below the number of variables")),
+            bdd.err()
+        );
+    }
+
     #[test]
     fn truth_table() {
         let bdd = BinaryDecisionDiagram::from_str(SIMPLE_BDD).unwrap();
@@ -0,0 +1,350 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::rc::Rc;
+
+use crate::bdd::builder::{DiagramBuilder, TRUE_NODE};
+use crate::bdd::{BinaryDecisionDiagram, BinaryOperator};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{Evaluate, FlowError};
+
+/// Live node count below which garbage is never collected automatically.
+const MIN_GC_THRESHOLD: usize = 1024;
+
+/// Owns the nodes of many related functions, sharing every common subgraph
+/// through one unique table and one computed cache.
+///
+/// Functions are handed out as [`Bdd`] handles. Nodes only reachable from
+/// dropped handles are reclaimed by [`BddManager::collect_garbage`], which
+/// also runs on its own before an operation once the table has doubled since
+/// the last collection.
+/// # Example
+/// ```
+/// use flow::bdd::{BddManager, BinaryOperator};
+/// use flow::Evaluate;
+/// let manager = BddManager::new(2);
+/// let x = manager.variable(0);
+/// let y = manager.variable(1);
+/// let and = x.apply(&y, BinaryOperator::And).unwrap();
+/// assert_eq!(vec![false, false, false, true], and.truth_table().unwrap());
+/// ```
+#[derive(Clone)]
+pub struct BddManager {
+    shared: Rc<RefCell<Shared>>,
+}
+
+/// A function stored in a [`BddManager`]. Handles of the same manager are
+/// equal exactly when their functions are equal.
+pub struct Bdd {
+    shared: Rc<RefCell<Shared>>,
+    root: usize,
+}
+
+struct Shared {
+    variables: usize,
+    builder: DiagramBuilder,
+    /// Number of live handles to each node that has any.
+    handles: HashMap<usize, usize>,
+    gc_threshold: usize,
+}
+
+impl Shared {
+    fn handle(shared: &Rc<RefCell<Self>>, root: usize) -> Bdd {
+        *shared.borrow_mut().handles.entry(root).or_insert(0) += 1;
+        Bdd {
+            shared: Rc::clone(shared),
+            root,
+        }
+    }
+
+    fn collect_garbage(&mut self) -> usize {
+        let roots: Vec<usize> = self.handles.keys().copied().collect();
+        let freed = self.builder.collect(roots);
+        self.gc_threshold = MIN_GC_THRESHOLD.max(2 * self.builder.live_nodes());
+        freed
+    }
+
+    /// Collect garbage if the table has grown past the threshold. Only safe
+    /// between operations, while every node in use is held by a handle.
+    fn maybe_collect_garbage(&mut self) {
+        if self.builder.live_nodes() >= self.gc_threshold {
+            self.collect_garbage();
+        }
+    }
+}
+
+impl BddManager {
+    /// An empty manager for functions over `variables` variables.
+    #[must_use]
+    pub fn new(variables: usize) -> Self {
+        Self {
+            shared: Rc::new(RefCell::new(Shared {
+                variables,
+                builder: DiagramBuilder::new(),
+                handles: HashMap::new(),
+                gc_threshold: MIN_GC_THRESHOLD,
+            })),
+        }
+    }
+
+    /// The number of variables functions of this manager are declared over.
+    #[must_use]
+    pub fn variables(&self) -> usize { self.shared.borrow().variables }
+
+    /// The function that always evaluates to `value`.
+    #[must_use]
+    pub fn constant(&self, value: bool) -> Bdd {
+        Shared::handle(&self.shared, DiagramBuilder::constant(value))
+    }
+
+    /// The function that evaluates to the value of `variable_id`. The manager
+    /// grows to include the variable if needed.
+    #[must_use]
+    pub fn variable(&self, variable_id: usize) -> Bdd {
+        let root = {
+            let mut shared = self.shared.borrow_mut();
+            shared.maybe_collect_garbage();
+            shared.variables = shared.variables.max(variable_id + 1);
+            shared.builder.variable(variable_id)
+        };
+        Shared::handle(&self.shared, root)
+    }
+
+    /// Add the function of a standalone diagram to this manager.
+    /// # Errors
+    /// * `EvaluationError` - the diagram contains a cycle
+    pub fn import(&self, bdd: &BinaryDecisionDiagram) -> Result<Bdd, FlowError> {
+        let root = {
+            let mut shared = self.shared.borrow_mut();
+            shared.maybe_collect_garbage();
+            shared.variables = shared.variables.max(bdd.variables);
+            shared.builder.import(bdd)?
+        };
+        Ok(Shared::handle(&self.shared, root))
+    }
+
+    /// The number of nodes currently stored, including both terminals and any
+    /// garbage not yet collected.
+    #[must_use]
+    pub fn node_count(&self) -> usize { self.shared.borrow().builder.live_nodes() }
+
+    /// Free every node no live handle can reach, returning how many were
+    /// freed. Clears the computed cache.
+    pub fn collect_garbage(&self) -> usize { self.shared.borrow_mut().collect_garbage() }
+}
+
+impl Debug for BddManager {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let shared = self.shared.borrow();
+        f.debug_struct("BddManager")
+            .field("variables", &shared.variables)
+            .field("nodes", &shared.builder.live_nodes())
+            .field("handles", &shared.handles.values().sum::<usize>())
+            .finish()
+    }
+}
+
+impl Bdd {
+    /// The manager this function is stored in.
+    #[must_use]
+    pub fn manager(&self) -> BddManager {
+        BddManager {
+            shared: Rc::clone(&self.shared),
+        }
+    }
+
+    /// Combine two functions of the same manager with a boolean operator.
+    /// # Errors
+    /// * `EvaluationError` - the functions belong to different managers
+    pub fn apply(&self, other: &Self, operator: BinaryOperator) -> Result<Self, FlowError> {
+        if !Rc::ptr_eq(&self.shared, &other.shared) {
+            return Err(EvaluationError("The diagrams belong to different managers"));
+        }
+        let root = {
+            let mut shared = self.shared.borrow_mut();
+            shared.maybe_collect_garbage();
+            shared.builder.apply(operator, self.root, other.root)
+        };
+        Ok(Shared::handle(&self.shared, root))
+    }
+
+    /// The complement of this function.
+    #[must_use]
+    pub fn negate(&self) -> Self {
+        let root = {
+            let mut shared = self.shared.borrow_mut();
+            shared.maybe_collect_garbage();
+            shared.builder.not(self.root)
+        };
+        Shared::handle(&self.shared, root)
+    }
+
+    /// Whether this is the constant function `value`.
+    #[must_use]
+    pub fn is_constant(&self, value: bool) -> bool { self.root == DiagramBuilder::constant(value) }
+
+    /// The number of nodes reachable from this function, including both
+    /// terminals.
+    #[must_use]
+    pub fn node_count(&self) -> usize { self.to_diagram().nodes.len() }
+
+    /// Copy this function out of the manager as a standalone diagram.
+    #[must_use]
+    pub fn to_diagram(&self) -> BinaryDecisionDiagram {
+        let shared = self.shared.borrow();
+        shared.builder.build(self.root, shared.variables)
+    }
+}
+
+impl Clone for Bdd {
+    fn clone(&self) -> Self { Shared::handle(&self.shared, self.root) }
+}
+
+impl Drop for Bdd {
+    fn drop(&mut self) {
+        let mut shared = self.shared.borrow_mut();
+        if let Some(count) = shared.handles.get_mut(&self.root) {
+            *count -= 1;
+            if *count == 0 {
+                shared.handles.remove(&self.root);
+            }
+        }
+    }
+}
+
+impl PartialEq for Bdd {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.shared, &other.shared) && self.root == other.root
+    }
+}
+
+impl Eq for Bdd {}
+
+impl Debug for Bdd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bdd").field("root", &self.root).finish()
+    }
+}
+
+impl Evaluate for Bdd {
+    fn eval(&self, values: &[bool]) -> Result<bool, FlowError> {
+        let shared = self.shared.borrow();
+        if values.len() < shared.variables {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let mut id = self.root;
+        while let Some((variable_id, low, high)) = shared.builder.decision(id) {
+            id = if values[variable_id] { high } else { low };
+        }
+        Ok(id == TRUE_NODE)
+    }
+
+    fn truth_table(&self) -> Result<Vec<bool>, FlowError> { self.to_diagram().truth_table() }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::{BddManager, BinaryDecisionDiagram, BinaryOperator};
+    use crate::Evaluate;
+
+    #[test]
+    fn shares_nodes_across_functions() {
+        let manager = BddManager::new(3);
+        let x = manager.variable(0);
+        let y = manager.variable(1);
+        let first = x.apply(&y, BinaryOperator::And).unwrap();
+        let second = y.apply(&x, BinaryOperator::And).unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.negate().negate(), first);
+    }
+
+    #[test]
+    fn tautology() {
+        let manager = BddManager::new(1);
+        let x = manager.variable(0);
+        let or = x.apply(&x.negate(), BinaryOperator::Or).unwrap();
+        assert!(or.is_constant(true));
+        assert_eq!(manager.constant(true), or);
+    }
+
+    #[test]
+    fn collects_dropped_functions() {
+        let manager = BddManager::new(3);
+        let x = manager.variable(0);
+        let y = manager.variable(1);
+        let z = manager.variable(2);
+        let kept = x.apply(&y, BinaryOperator::Xor).unwrap();
+        let dropped = kept.apply(&z, BinaryOperator::Or).unwrap();
+        let before = manager.node_count();
+        drop(dropped);
+        assert!(manager.collect_garbage() > 0);
+        assert!(manager.node_count() < before);
+        assert_eq!(0, manager.collect_garbage());
+        assert_eq!(
+            vec![false, true, true, false, false, true, true, false],
+            kept.truth_table().unwrap()
+        );
+    }
+
+    #[test]
+    fn clone_keeps_nodes_alive() {
+        let manager = BddManager::new(2);
+        let and = manager
+            .variable(0)
+            .apply(&manager.variable(1), BinaryOperator::And)
+            .unwrap();
+        let copy = and.clone();
+        drop(and);
+        manager.collect_garbage();
+        assert_eq!(vec![false, false, false, true], copy.truth_table().unwrap());
+    }
+
+    #[test]
+    fn different_managers() {
+        let x = BddManager::new(1).variable(0);
+        let y = BddManager::new(1).variable(0);
+        assert_ne!(x, y);
+        assert!(x.apply(&y, BinaryOperator::And).is_err());
+    }
+
+    #[test]
+    fn import_and_export() {
+        let bdd = BinaryDecisionDiagram::from_truth_table(&[false, true, true, true]).unwrap();
+        let manager = BddManager::new(2);
+        let imported = manager.import(&bdd).unwrap();
+        assert_eq!(bdd.truth_table(), imported.to_diagram().truth_table());
+        assert_eq!(Ok(true), imported.eval(&[true, false]));
+        assert!(imported.eval(&[true]).is_err());
+    }
+
+    #[test]
+    fn import_deep_chain() {
+        let manager = BddManager::new(DEEP);
+        let imported = manager.import(&and_chain(DEEP)).unwrap();
+        assert_eq!(DEEP + 2, imported.node_count());
+        assert_eq!(Ok(true), imported.eval(&vec![true; DEEP]));
+        assert!(imported
+            .apply(&imported.negate(), BinaryOperator::And)
+            .unwrap()
+            .is_constant(false));
+    }
+}
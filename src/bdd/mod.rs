@@ -14,40 +14,253 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug, Default)]
+use crate::FlowError;
+use crate::FlowError::{EvaluationError, ParseError};
+
+#[derive(Clone, Debug, Default)]
 pub struct BinaryDecisionDiagram {
     variables: usize,
-    nodes: HashMap<usize, BinaryNode>,
-    entry_node: usize,
+    /// Arena of nodes in level order: the entry node, the other decision
+    /// nodes by ascending variable id, then the terminals.
+    nodes: Vec<BinaryNode>,
+    /// The id each node had in its source, by position in `nodes`.
+    node_ids: Vec<usize>,
+    /// Position in `nodes` of each source id.
+    indices: HashMap<usize, NodeIndex>,
+    entry_node: NodeIndex,
 }
 
+mod aiger;
+mod apply;
+pub(crate) mod builder;
+mod complement;
+mod crossbar;
+mod dimacs;
 mod display;
 mod eval;
+mod manager;
 mod parse;
+mod reduce;
+mod sat;
+mod stats;
+mod table;
+mod verilog;
+
+pub use self::aiger::AigerFormat;
+pub use self::complement::ComplementedDiagram;
+pub use self::manager::{Bdd, BddManager};
+pub use self::stats::Statistics;
+pub(crate) use self::verilog::is_identifier;
+pub use self::verilog::VerilogStyle;
+
+/// Two-input boolean operators that can be applied to a pair of diagrams.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BinaryOperator {
+    And,
+    Or,
+    Xor,
+    Nand,
+    Nor,
+    Xnor,
+    Implies,
+}
+
+impl BinaryDecisionDiagram {
+    /// The number of variables the diagram is declared over.
+    #[must_use]
+    pub fn variables(&self) -> usize { self.variables }
+
+    /// The number of nodes, including terminals.
+    #[must_use]
+    pub fn node_count(&self) -> usize { self.nodes.len() }
+
+    /// The ids nodes had in their source, in level order: the entry node,
+    /// the other decision nodes by ascending variable id, then the terminals.
+    #[must_use]
+    pub fn node_ids(&self) -> &[usize] { &self.node_ids }
+
+    /// The level-order position of the node with source id `id`.
+    #[must_use]
+    pub fn node_position(&self, id: usize) -> Option<usize> {
+        self.indices.get(&id).map(|index| index.0)
+    }
+
+    /// Lay out nodes given by source id into the arena, resolving the child
+    /// ids of every decision node.
+    /// # Errors
+    /// * `ParseError` - an id is defined twice, a child or the entry node is
+    ///   not defined, or a node can reach itself
+    pub(crate) fn arrange(
+        variables: usize,
+        mut source: Vec<(usize, SourceNode)>,
+        entry_id: usize,
+    ) -> Result<Self, FlowError> {
+        // The entry node leads so that a written diagram starts at its root
+        // even when paths disagree on the variable order.
+        source.sort_by_key(|(id, node)| match node {
+            SourceNode::Decision(variable_id, _, _) => (false, *id != entry_id, *variable_id, *id),
+            SourceNode::Terminal(value) => (true, false, usize::from(*value), *id),
+        });
+
+        let mut indices = HashMap::with_capacity(source.len());
+        for (index, (id, _)) in source.iter().enumerate() {
+            if indices.insert(*id, NodeIndex(index)).is_some() {
+                return Err(ParseError("Node id defined more than once"));
+            }
+        }
+        let resolve = |id: usize| {
+            indices
+                .get(&id)
+                .copied()
+                .ok_or(ParseError("Node refers to a missing node"))
+        };
+
+        let nodes = source
+            .iter()
+            .map(|(_, node)| match *node {
+                SourceNode::Decision(variable_id, _, _) if variable_id >= variables => {
+                    Err(ParseError("Node tests a variable outside the diagram"))
+                },
+                SourceNode::Decision(variable_id, node_if_false, node_if_true) => {
+                    Ok(BinaryNode::Decision(DecisionNode::new_node(
+                        resolve(node_if_false)?,
+                        resolve(node_if_true)?,
+                        variable_id,
+                    )))
+                },
+                SourceNode::Terminal(value) => Ok(BinaryNode::Terminal(value)),
+            })
+            .collect::<Result<Vec<_>, FlowError>>()?;
+        let entry_node = resolve(entry_id)?;
+        if has_cycle(&nodes) {
+            return Err(ParseError("Node can reach itself through its children"));
+        }
+
+        Ok(Self {
+            variables,
+            nodes,
+            node_ids: source.into_iter().map(|(id, _)| id).collect(),
+            indices,
+            entry_node,
+        })
+    }
+
+    /// Nodes reachable from the entry node, children before parents, found
+    /// without recursion.
+    /// # Errors
+    /// * `EvaluationError` - a node is missing or the diagram contains a cycle
+    fn topological_order(&self) -> Result<Vec<NodeIndex>, FlowError> {
+        let mut order = Vec::new();
+        let mut done: HashSet<NodeIndex> = HashSet::new();
+        let mut in_progress: HashSet<NodeIndex> = HashSet::new();
+        let mut stack = vec![(self.entry_node, false)];
+        while let Some((id, expanded)) = stack.pop() {
+            if done.contains(&id) {
+                continue;
+            }
+            let node = self
+                .nodes
+                .get(id.0)
+                .ok_or(EvaluationError("Could not traverse to next node"))?;
+            match node {
+                BinaryNode::Decision(node) if !expanded => {
+                    if !in_progress.insert(id) {
+                        return Err(EvaluationError("The diagram contains a cycle"));
+                    }
+                    stack.push((id, true));
+                    stack.push((node.decision_map.0, false));
+                    stack.push((node.decision_map.1, false));
+                },
+                _ => {
+                    done.insert(id);
+                    order.push(id);
+                },
+            }
+        }
+        Ok(order)
+    }
+}
+
+/// Whether any node can reach itself, found by an iterative depth-first
+/// search so deep diagrams cannot overflow the stack.
+fn has_cycle(nodes: &[BinaryNode]) -> bool {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        Unvisited,
+        OnPath,
+        Done,
+    }
+    let children = |index: usize| match &nodes[index] {
+        BinaryNode::Decision(node) => [node.decision_map.0 .0, node.decision_map.1 .0],
+        BinaryNode::Terminal(_) => [index, index],
+    };
 
-#[derive(Debug, PartialEq)]
+    let mut state = vec![State::Unvisited; nodes.len()];
+    for start in 0..nodes.len() {
+        if state[start] != State::Unvisited {
+            continue;
+        }
+        state[start] = State::OnPath;
+        // Each entry is a node on the current path and how many of its
+        // children have been followed.
+        let mut path = vec![(start, 0)];
+        while let Some((index, followed)) = path.last_mut() {
+            let index = *index;
+            if *followed == 2 || matches!(nodes[index], BinaryNode::Terminal(_)) {
+                state[index] = State::Done;
+                path.pop();
+                continue;
+            }
+            let child = children(index)[*followed];
+            *followed += 1;
+            match state[child] {
+                State::OnPath => return true,
+                State::Unvisited => {
+                    state[child] = State::OnPath;
+                    path.push((child, 0));
+                },
+                State::Done => {},
+            }
+        }
+    }
+    false
+}
+
+/// Position of a node in the arena of a [`BinaryDecisionDiagram`].
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+struct NodeIndex(usize);
+
+/// A node as written in a source, with children referred to by id.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum SourceNode {
+    /// variable id, id if false, id if true
+    Decision(usize, usize, usize),
+    Terminal(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
 enum BinaryNode {
     Decision(DecisionNode),
     Terminal(bool),
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 struct DecisionNode {
     pub variable_id: usize,
-    decision_map: (usize, usize),
+    decision_map: (NodeIndex, NodeIndex),
 }
 
 impl DecisionNode {
-    pub fn new_node(node_if_false: usize, node_if_true: usize, variable_id: usize) -> Self {
+    pub fn new_node(node_if_false: NodeIndex, node_if_true: NodeIndex, variable_id: usize) -> Self {
         Self {
             variable_id,
             decision_map: (node_if_false, node_if_true),
         }
     }
 
-    pub fn evaluate(&self, variable: bool) -> usize {
+    pub fn evaluate(&self, variable: bool) -> NodeIndex {
         if variable {
             self.decision_map.1
         } else {
@@ -58,19 +271,33 @@ impl DecisionNode {
 
 #[cfg(test)]
 mod test {
-    use crate::bdd::DecisionNode;
+    use crate::bdd::{BinaryDecisionDiagram, DecisionNode, NodeIndex, SourceNode};
+    use crate::FlowError::ParseError;
 
     #[test]
     fn false_variable() {
-        let node = DecisionNode::new_node(1, 0, 0);
+        let node = DecisionNode::new_node(NodeIndex(1), NodeIndex(0), 0);
 
-        assert_eq!(1, node.evaluate(false));
+        assert_eq!(NodeIndex(1), node.evaluate(false));
     }
 
     #[test]
     fn true_variable() {
-        let node = DecisionNode::new_node(0, 1, 0);
+        let node = DecisionNode::new_node(NodeIndex(0), NodeIndex(1), 0);
+
+        assert_eq!(NodeIndex(1), node.evaluate(true));
+    }
 
-        assert_eq!(1, node.evaluate(true));
+    #[test]
+    fn arrange_variable_out_of_range() {
+        let source = vec![
+            (0, SourceNode::Decision(2, 1, 2)),
+            (1, SourceNode::Terminal(false)),
+            (2, SourceNode::Terminal(true)),
+        ];
+        assert_eq!(
+            Some(ParseError("Node tests a variable outside the diagram")),
+            BinaryDecisionDiagram::arrange(2, source, 0).err()
+        );
     }
 }
@@ -14,18 +14,16 @@
  * limitations under the License.
  */
 
-use std::collections::HashMap;
+use std::collections::HashSet;
 use std::str::FromStr;
 
-use crate::bdd::BinaryNode::{Decision, Terminal};
-use crate::bdd::{BinaryDecisionDiagram, DecisionNode};
+use crate::bdd::{BinaryDecisionDiagram, SourceNode};
 use crate::FlowError;
 use crate::FlowError::ParseError;
 
 impl FromStr for BinaryDecisionDiagram {
     type Err = FlowError;
 
-    #[allow(clippy::cast_sign_loss)]
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lines = s.lines();
         let mut var_line = lines
@@ -46,9 +44,22 @@ impl FromStr for BinaryDecisionDiagram {
             .ok_or(ParseError("Node line does not specify number"))?
             .parse::<usize>()?;
 
+        let mut lines = lines.peekable();
+        let declared_root = match lines.peek().map(|line| line.split_ascii_whitespace()) {
+            Some(mut root_line) if root_line.clone().next() == Some("root") => {
+                lines.next();
+                Some(
+                    root_line
+                        .nth(1)
+                        .ok_or(ParseError("Root line does not specify a node"))?
+                        .parse::<usize>()?,
+                )
+            },
+            _ => None,
+        };
+
         let variables = num_vars;
-        let mut nodes = HashMap::with_capacity(num_nodes);
-        let mut entry_node: Option<usize> = None;
+        let mut nodes = Vec::with_capacity(num_nodes.min(s.len()));
         for line in lines {
             let mut split = line.split_ascii_whitespace();
             let node_num = split
@@ -68,32 +79,37 @@ impl FromStr for BinaryDecisionDiagram {
                 .ok_or(ParseError("Var ID not present"))?
                 .parse::<usize>()?;
 
-            if node_if_true < 0 && node_if_false < 0 {
-                nodes.insert(node_num, Terminal(var_id == 1));
-                continue;
-            }
-
-            if entry_node.is_none() {
-                entry_node = Some(node_num);
+            let (node_if_true, node_if_false) = match (
+                usize::try_from(node_if_true),
+                usize::try_from(node_if_false),
+            ) {
+                (Err(_), Err(_)) => {
+                    if var_id > 1 {
+                        return Err(ParseError("Terminal value must be 0 or 1"));
+                    }
+                    nodes.push((node_num, SourceNode::Terminal(var_id == 1)));
+                    continue;
+                },
+                (Ok(node_if_true), Ok(node_if_false)) => (node_if_true, node_if_false),
+                _ => return Err(ParseError("Only terminal nodes have negative children")),
+            };
+            if var_id >= variables {
+                return Err(ParseError("Var ID is not below the number of variables"));
             }
 
-            nodes.insert(
+            nodes.push((
                 node_num,
-                Decision(DecisionNode::new_node(
-                    node_if_false as usize,
-                    node_if_true as usize,
-                    var_id,
-                )),
-            );
+                SourceNode::Decision(var_id, node_if_false, node_if_true),
+            ));
         }
 
         let mut has_false = false;
         let mut has_true = false;
         nodes
-            .values()
-            .filter_map(|node| match node {
-                Decision(_) => None,
-                Terminal(val) => Some(val),
+            .iter()
+            .filter_map(|(_, node)| match node {
+                SourceNode::Decision(..) => None,
+                SourceNode::Terminal(val) => Some(val),
             })
             .for_each(|&terminal| {
                 if terminal {
@@ -107,11 +123,38 @@ impl FromStr for BinaryDecisionDiagram {
             return Err(ParseError("Not both types of terminal nodes."));
         }
 
-        Ok(Self {
-            variables,
-            nodes,
-            entry_node: entry_node.ok_or(ParseError("No entry node was set"))?,
+        let entry_node = match declared_root {
+            Some(root) if nodes.iter().any(|&(id, _)| id == root) => root,
+            Some(_) => return Err(ParseError("Root is not a node of the diagram")),
+            None => infer_root(&nodes)?,
+        };
+        Self::arrange(variables, nodes, entry_node)
+    }
+}
+
+/// The only decision node that is not a child of another node.
+fn infer_root(nodes: &[(usize, SourceNode)]) -> Result<usize, FlowError> {
+    let children: HashSet<usize> = nodes
+        .iter()
+        .filter_map(|(_, node)| match *node {
+            SourceNode::Decision(_, node_if_false, node_if_true) => {
+                Some([node_if_false, node_if_true])
+            },
+            SourceNode::Terminal(_) => None,
         })
+        .flatten()
+        .collect();
+    let mut parentless = nodes.iter().filter_map(|&(id, node)| {
+        matches!(node, SourceNode::Decision(..) if !children.contains(&id)).then_some(id)
+    });
+    match (parentless.next(), parentless.next()) {
+        (Some(root), None) => Ok(root),
+        (Some(_), Some(_)) => Err(ParseError(
+            "More than one node has no parent, so a root line is needed",
+        )),
+        (None, _) => Err(ParseError(
+            "No decision node is without a parent, so a root line is needed",
+        )),
     }
 }
 
@@ -121,11 +164,13 @@ mod test {
 
     use crate::bdd::BinaryNode::{Decision, Terminal};
     use crate::bdd::{BinaryDecisionDiagram, DecisionNode};
+    use crate::Evaluate;
+    use crate::FlowError::ParseError;
 
     const FREE_BDD_2: &str = "vars 2
 nodes 4
-1 4 2 1
-2 4 3 2
+1 4 2 0
+2 4 3 1
 3 -1 -1 0
 4 -1 -1 1";
 
@@ -133,18 +178,21 @@ nodes 4
     fn from_string() {
         let bdd = BinaryDecisionDiagram::from_str(FREE_BDD_2).unwrap();
 
+        let index = |id: usize| bdd.indices[&id];
+        let node = |id: usize| &bdd.nodes[index(id).0];
+
         assert_eq!(2, bdd.variables);
         assert_eq!(4, bdd.nodes.len());
         assert_eq!(
-            &Decision(DecisionNode::new_node(2, 4, 1)),
-            bdd.nodes.get(&1).unwrap()
+            &Decision(DecisionNode::new_node(index(2), index(4), 0)),
+            node(1)
         );
         assert_eq!(
-            &Decision(DecisionNode::new_node(3, 4, 2)),
-            bdd.nodes.get(&2).unwrap()
+            &Decision(DecisionNode::new_node(index(3), index(4), 1)),
+            node(2)
         );
-        assert_eq!(&Terminal(false), bdd.nodes.get(&3).unwrap());
-        assert_eq!(&Terminal(true), bdd.nodes.get(&4).unwrap());
+        assert_eq!(&Terminal(false), node(3));
+        assert_eq!(&Terminal(true), node(4));
     }
 
     #[test]
@@ -178,6 +226,36 @@ nodes 1
         assert!(bdd.is_err());
     }
 
+    #[test]
+    fn one_negative_child() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 -1 2 0
+1 -1 -1 1
+2 -1 -1 0",
+        );
+        assert!(bdd.is_err());
+    }
+
+    #[test]
+    fn terminal_value() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 1 2 0
+1 -1 -1 2
+2 -1 -1 0",
+        );
+        assert!(bdd.is_err());
+    }
+
+    #[test]
+    fn huge_node_count() {
+        let bdd = BinaryDecisionDiagram::from_str(&format!("vars 1\nnodes {}", usize::MAX));
+        assert!(bdd.is_err());
+    }
+
     #[test]
     fn non_matching_vars() {
         let bdd = BinaryDecisionDiagram::from_str(
@@ -230,6 +308,151 @@ nodes 2
         assert!(bdd.is_err());
     }
 
+    #[test]
+    fn missing_child() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 1 5 0
+1 -1 -1 0
+2 -1 -1 1",
+        );
+        assert!(bdd.is_err());
+    }
+
+    #[test]
+    fn cycle() {
+        let self_loop = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 0 0 0
+1 -1 -1 0
+2 -1 -1 1",
+        );
+        assert!(self_loop.is_err());
+        let through_child = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 4
+0 1 0 0
+1 2 3 0
+2 -1 -1 0
+3 -1 -1 1",
+        );
+        assert!(through_child.is_err());
+    }
+
+    #[test]
+    fn root_line() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 2
+nodes 4
+root 3
+1 -1 -1 0
+2 -1 -1 1
+0 2 1 1
+3 0 1 0",
+        )
+        .unwrap();
+        assert_eq!(3, bdd.node_ids()[0]);
+        assert_eq!(Ok(true), bdd.eval(&[true, true]));
+        assert_eq!(Ok(false), bdd.eval(&[false, true]));
+    }
+
+    #[test]
+    fn inferred_root() {
+        // The root is not the first decision line.
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 2
+nodes 4
+0 2 1 1
+1 -1 -1 0
+2 -1 -1 1
+3 0 1 0",
+        )
+        .unwrap();
+        assert_eq!(3, bdd.node_ids()[0]);
+    }
+
+    #[test]
+    fn ambiguous_root() {
+        let text = "vars 2
+nodes 4
+0 2 1 1
+3 2 1 0
+1 -1 -1 0
+2 -1 -1 1";
+        assert!(BinaryDecisionDiagram::from_str(text).is_err());
+        let text = text.replacen("nodes 4", "nodes 4\nroot 3", 1);
+        assert_eq!(
+            3,
+            BinaryDecisionDiagram::from_str(&text).unwrap().node_ids()[0]
+        );
+    }
+
+    #[test]
+    fn missing_root() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+root 7
+0 1 2 0
+1 -1 -1 0
+2 -1 -1 1",
+        );
+        assert!(bdd.is_err());
+    }
+
+    #[test]
+    fn terminal_root() {
+        let constant = BinaryDecisionDiagram::constant(true, 2);
+        let parsed: BinaryDecisionDiagram = constant.to_string().parse().unwrap();
+        assert_eq!(Ok(vec![true; 4]), parsed.truth_table());
+    }
+
+    #[test]
+    fn variable_out_of_range() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 3
+0 2 1 1
+1 -1 -1 0
+2 -1 -1 1",
+        );
+        assert_eq!(
+            Some(ParseError("Var ID is not below the number of variables")),
+            bdd.err()
+        );
+    }
+
+    #[test]
+    fn duplicate_node_id() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 1
+nodes 4
+0 1 2 0
+1 -1 -1 0
+2 -1 -1 1
+2 -1 -1 0",
+        );
+        assert!(bdd.is_err());
+    }
+
+    #[test]
+    fn level_ordered() {
+        let bdd = BinaryDecisionDiagram::from_str(
+            "vars 2
+nodes 4
+4 -1 -1 1
+3 -1 -1 0
+1 4 2 1
+2 4 3 0",
+        )
+        .unwrap();
+        assert_eq!(&[1, 2, 3, 4], bdd.node_ids());
+        assert_eq!(Some(1), bdd.node_position(2));
+        assert_eq!(None, bdd.node_position(0));
+    }
+
     #[test]
     fn parseable() {
         let bdd = BinaryDecisionDiagram::from_str(
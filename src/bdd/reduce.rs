@@ -0,0 +1,116 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bdd::builder::DiagramBuilder;
+use crate::bdd::BinaryDecisionDiagram;
+use crate::FlowError;
+
+impl BinaryDecisionDiagram {
+    /// The reduced ordered diagram of the same function: no node tests a
+    /// variable whose children are equal, no two nodes are equivalent, and
+    /// every path tests variables in ascending id order.
+    /// # Errors
+    /// * `EvaluationError` - the diagram contains a cycle
+    /// # Example
+    /// ```
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// let bdd: BinaryDecisionDiagram = "vars 1
+    /// nodes 3
+    /// 0 1 1 0
+    /// 1 -1 -1 1
+    /// 2 -1 -1 0"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(3, bdd.node_count());
+    /// assert_eq!(2, bdd.reduce().unwrap().node_count());
+    /// ```
+    pub fn reduce(&self) -> Result<Self, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(self)?;
+        Ok(builder.build(root, self.variables))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::Evaluate;
+
+    #[test]
+    fn reduce_merges_equivalent_nodes() {
+        let bdd: BinaryDecisionDiagram = "vars 2
+nodes 5
+0 1 2 0
+1 3 4 1
+2 3 4 1
+3 -1 -1 1
+4 -1 -1 0"
+            .parse()
+            .unwrap();
+        let reduced = bdd.reduce().unwrap();
+        assert_eq!(3, reduced.node_count());
+        assert_eq!(bdd.truth_table(), reduced.truth_table());
+    }
+
+    /// Reducing twice gives the same diagram, though the text differs because
+    /// nodes are numbered afresh each time.
+    #[test]
+    fn reduce_is_idempotent() {
+        let bdd: BinaryDecisionDiagram = "vars 4
+nodes 7
+root 2
+2 5 0 2
+1 6 3 0
+4 5 6 0
+5 3 3 1
+0 1 4 3
+3 -1 -1 0
+6 -1 -1 1"
+            .parse()
+            .unwrap();
+        let reduced = bdd.reduce().unwrap();
+        let again = reduced.reduce().unwrap();
+        assert_eq!(reduced.node_count(), again.node_count());
+        assert_eq!(bdd.truth_table(), again.truth_table());
+    }
+
+    /// A free diagram tests x2 both before and after x5; putting it in
+    /// ascending order needs more nodes, not fewer.
+    #[test]
+    fn reduce_can_grow_unordered_diagram() {
+        let bdd: BinaryDecisionDiagram = "vars 7
+nodes 7
+root 3
+3 0 6 1
+6 5 2 2
+0 4 1 4
+1 5 6 5
+4 1 2 6
+5 -1 -1 0
+2 -1 -1 1"
+            .parse()
+            .unwrap();
+        let reduced = bdd.reduce().unwrap();
+        assert!(reduced.node_count() > bdd.node_count());
+        assert_eq!(bdd.truth_table(), reduced.truth_table());
+    }
+
+    #[test]
+    fn deep_chain() {
+        assert_eq!(DEEP + 2, and_chain(DEEP).reduce().unwrap().node_count());
+    }
+}
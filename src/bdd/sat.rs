@@ -0,0 +1,195 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE};
+use crate::bdd::BinaryDecisionDiagram;
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+impl BinaryDecisionDiagram {
+    /// Whether any assignment of the variables evaluates to true.
+    /// # Errors
+    /// * `EvaluationError` - the diagram references a missing node or contains
+    ///   a cycle
+    pub fn is_satisfiable(&self) -> Result<bool, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        Ok(builder.import(self)? != FALSE_NODE)
+    }
+
+    /// A satisfying assignment, if one exists. Variables the function does not
+    /// depend on along the chosen path are set to false.
+    /// # Errors
+    /// * `EvaluationError` - the diagram references a missing node or contains
+    ///   a cycle
+    pub fn model(&self) -> Result<Option<Vec<bool>>, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let mut id = builder.import(self)?;
+        if id == FALSE_NODE {
+            return Ok(None);
+        }
+
+        let mut values = vec![false; self.variables];
+        while let Some((variable_id, low, high)) = builder.decision(id) {
+            // Every node except the false terminal reaches true in a reduced
+            // diagram, so only stepping straight onto false must be avoided.
+            if low == FALSE_NODE {
+                if variable_id >= values.len() {
+                    values.resize(variable_id + 1, false);
+                }
+                values[variable_id] = true;
+                id = high;
+            } else {
+                id = low;
+            }
+        }
+        Ok(Some(values))
+    }
+
+    /// The number of assignments of the declared variables that evaluate to
+    /// true.
+    /// # Errors
+    /// * `EvaluationError` - the diagram references a missing node, contains a
+    ///   cycle, or the count does not fit in a `u128`
+    pub fn model_count(&self) -> Result<u128, FlowError> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(self)?;
+        let variables = builder.build(root, self.variables).variables;
+
+        let mut counts: HashMap<usize, u128> = HashMap::new();
+        let count = count_node(&builder, root, &mut counts, variables)?;
+        scale(count, level(&builder, root, variables))
+    }
+}
+
+/// The variable id tested by a node, with terminals below every variable.
+fn level(builder: &DiagramBuilder, id: usize, variables: usize) -> usize {
+    match builder.decision(id) {
+        Some((variable_id, _, _)) => variable_id,
+        None => variables,
+    }
+}
+
+fn scale(count: u128, skipped: usize) -> Result<u128, FlowError> {
+    if count == 0 {
+        return Ok(0);
+    }
+    u32::try_from(skipped)
+        .ok()
+        .and_then(|skipped| 1u128.checked_shl(skipped))
+        .and_then(|factor| count.checked_mul(factor))
+        .ok_or(EvaluationError("Model count does not fit in 128 bits"))
+}
+
+/// Count assignments to the variables from the node's level downwards,
+/// children before parents so deep diagrams cannot overflow the stack.
+fn count_node(
+    builder: &DiagramBuilder,
+    root: usize,
+    counts: &mut HashMap<usize, u128>,
+    variables: usize,
+) -> Result<u128, FlowError> {
+    let mut stack = vec![(root, false)];
+    while let Some((id, expanded)) = stack.pop() {
+        if counts.contains_key(&id) {
+            continue;
+        }
+        match builder.decision(id) {
+            None => {
+                counts.insert(id, u128::from(id != FALSE_NODE));
+            },
+            Some((_, low, high)) if !expanded => {
+                stack.push((id, true));
+                stack.push((high, false));
+                stack.push((low, false));
+            },
+            Some((variable_id, low, high)) => {
+                let mut count: u128 = 0;
+                for child in [low, high] {
+                    let skipped = level(builder, child, variables) - variable_id - 1;
+                    count = count
+                        .checked_add(scale(counts[&child], skipped)?)
+                        .ok_or(EvaluationError("Model count does not fit in 128 bits"))?;
+                }
+                counts.insert(id, count);
+            },
+        }
+    }
+    Ok(counts[&root])
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::Evaluate;
+
+    const FREE_BDD_2: &str = "vars 2
+nodes 4
+1 4 2 1
+2 4 3 0
+3 -1 -1 0
+4 -1 -1 1";
+
+    #[test]
+    fn satisfiable() {
+        let bdd: BinaryDecisionDiagram = FREE_BDD_2.parse().unwrap();
+        assert_eq!(Ok(true), bdd.is_satisfiable());
+    }
+
+    #[test]
+    fn unsatisfiable() {
+        let bdd = BinaryDecisionDiagram::from_dimacs("p cnf 1 2\n1 0\n-1 0").unwrap();
+        assert_eq!(Ok(false), bdd.is_satisfiable());
+        assert_eq!(Ok(None), bdd.model());
+        assert_eq!(Ok(0), bdd.model_count());
+    }
+
+    #[test]
+    fn model_evaluates_true() {
+        let bdd: BinaryDecisionDiagram = FREE_BDD_2.parse().unwrap();
+        let model = bdd.model().unwrap().unwrap();
+        assert_eq!(Ok(true), bdd.eval(&model));
+    }
+
+    #[test]
+    fn model_count_matches_truth_table() {
+        let bdd: BinaryDecisionDiagram = FREE_BDD_2.parse().unwrap();
+        let expected = bdd.truth_table().unwrap().iter().filter(|&&b| b).count();
+        assert_eq!(Ok(expected as u128), bdd.model_count());
+    }
+
+    #[test]
+    fn model_count_counts_unused_variables() {
+        let bdd = BinaryDecisionDiagram::from_dimacs("p cnf 4 1\n2 0").unwrap();
+        assert_eq!(Ok(8), bdd.model_count());
+    }
+
+    #[test]
+    fn model_count_overflow() {
+        let bdd = BinaryDecisionDiagram::constant(true, 128);
+        assert!(bdd.model_count().is_err());
+    }
+
+    #[test]
+    fn deep_chain() {
+        let bdd = and_chain(DEEP);
+        assert_eq!(Ok(true), bdd.is_satisfiable());
+        assert_eq!(Ok(Some(vec![true; DEEP])), bdd.model());
+        assert_eq!(Ok(1), bdd.model_count());
+    }
+}
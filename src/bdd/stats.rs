@@ -0,0 +1,328 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::bdd::{BinaryDecisionDiagram, BinaryNode};
+
+/// The shape of a diagram, as reported by
+/// [`BinaryDecisionDiagram::statistics`]. Everything but `nodes` and
+/// `unreachable` only counts nodes reachable from the root.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Statistics {
+    /// number of declared variables
+    pub variables: usize,
+    /// source id of the root
+    pub root: usize,
+    /// all nodes, including terminals and unreachable ones
+    pub nodes: usize,
+    /// nodes the root cannot reach
+    pub unreachable: usize,
+    /// decision nodes testing each variable, by variable id; these are the
+    /// levels of an ordered diagram
+    pub level_sizes: Vec<usize>,
+    /// the most decision nodes on a path from the root to a terminal
+    pub depth: usize,
+    /// the tests along a path with `depth` decision nodes, as variable id and
+    /// value
+    pub longest_path: Vec<(usize, bool)>,
+    /// the tests along a path with the fewest decision nodes
+    pub shortest_path: Vec<(usize, bool)>,
+    /// variables tested by some node, ascending
+    pub support: Vec<usize>,
+    /// declared variables that no node tests
+    pub unused: Vec<usize>,
+    /// false and true terminal nodes
+    pub terminals: (usize, usize),
+    /// whether one variable order agrees with every path
+    pub ordered: bool,
+    /// whether no node has equal children, no two nodes test the same
+    /// variable with the same children and each terminal value appears once
+    pub reduced: bool,
+}
+
+impl BinaryDecisionDiagram {
+    /// Counts and structural properties of the diagram, for a quick health
+    /// report.
+    /// # Example
+    /// ```
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// let bdd = BinaryDecisionDiagram::from_truth_table(&[false, false, false, true]).unwrap();
+    /// let statistics = bdd.statistics();
+    /// assert_eq!(2, statistics.depth);
+    /// assert_eq!(vec![(0, false)], statistics.shortest_path);
+    /// assert!(statistics.ordered && statistics.reduced);
+    /// ```
+    #[must_use]
+    pub fn statistics(&self) -> Statistics {
+        let decision = |index: usize| match &self.nodes[index] {
+            BinaryNode::Decision(node) => Some((
+                node.variable_id,
+                node.decision_map.0 .0,
+                node.decision_map.1 .0,
+            )),
+            BinaryNode::Terminal(_) => None,
+        };
+        let order = self.post_order();
+
+        let mut longest = vec![0; self.nodes.len()];
+        let mut shortest = vec![0; self.nodes.len()];
+        let mut level_sizes = vec![0; self.variables];
+        let mut terminals = (0, 0);
+        let mut triples = HashSet::new();
+        let mut reduced = true;
+        // Pairs of variables tested one straight after the other.
+        let mut follows: HashSet<(usize, usize)> = HashSet::new();
+        for &index in &order {
+            let Some((variable_id, low, high)) = decision(index) else {
+                if let BinaryNode::Terminal(value) = self.nodes[index] {
+                    let count = if value {
+                        &mut terminals.1
+                    } else {
+                        &mut terminals.0
+                    };
+                    *count += 1;
+                    reduced &= *count == 1;
+                }
+                continue;
+            };
+            longest[index] = 1 + longest[low].max(longest[high]);
+            shortest[index] = 1 + shortest[low].min(shortest[high]);
+            if variable_id >= level_sizes.len() {
+                level_sizes.resize(variable_id + 1, 0);
+            }
+            level_sizes[variable_id] += 1;
+            reduced &= low != high && triples.insert((variable_id, low, high));
+            for child in [low, high] {
+                if let Some((child_variable, _, _)) = decision(child) {
+                    follows.insert((variable_id, child_variable));
+                }
+            }
+        }
+
+        let path = |lengths: &[usize], take_longer: bool| {
+            let mut path = Vec::new();
+            let mut index = self.entry_node.0;
+            while let Some((variable_id, low, high)) = decision(index) {
+                let value = if take_longer {
+                    lengths[high] > lengths[low]
+                } else {
+                    lengths[high] < lengths[low]
+                };
+                path.push((variable_id, value));
+                index = if value { high } else { low };
+            }
+            path
+        };
+        let (support, unused): (Vec<usize>, Vec<usize>) =
+            (0..level_sizes.len()).partition(|&variable_id| level_sizes[variable_id] > 0);
+
+        Statistics {
+            variables: self.variables,
+            root: self.node_ids[self.entry_node.0],
+            nodes: self.nodes.len(),
+            unreachable: self.nodes.len() - order.len(),
+            depth: longest[self.entry_node.0],
+            longest_path: path(&longest, true),
+            shortest_path: path(&shortest, false),
+            support,
+            unused: unused
+                .into_iter()
+                .filter(|&variable_id| variable_id < self.variables)
+                .collect(),
+            level_sizes,
+            terminals,
+            ordered: is_acyclic(&follows),
+            reduced,
+        }
+    }
+
+    /// Positions of the nodes reachable from the entry node, children before
+    /// their parents.
+    fn post_order(&self) -> Vec<usize> {
+        let mut visited = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![(self.entry_node.0, false)];
+        while let Some((index, expanded)) = stack.pop() {
+            if expanded {
+                order.push(index);
+                continue;
+            }
+            if visited[index] {
+                continue;
+            }
+            visited[index] = true;
+            stack.push((index, true));
+            if let BinaryNode::Decision(node) = &self.nodes[index] {
+                let (low, high) = node.decision_map;
+                stack.extend(
+                    [high.0, low.0]
+                        .into_iter()
+                        .filter(|&child| !visited[child])
+                        .map(|child| (child, false)),
+                );
+            }
+        }
+        order
+    }
+}
+
+/// Whether the "tested before" relation between variables has no cycle, a
+/// variable tested straight after itself included, so that some order of the
+/// variables agrees with every path.
+fn is_acyclic(follows: &HashSet<(usize, usize)>) -> bool {
+    let mut incoming: HashMap<usize, usize> = HashMap::new();
+    let mut after: HashMap<usize, Vec<usize>> = HashMap::new();
+    for &(before, later) in follows {
+        incoming.entry(before).or_default();
+        *incoming.entry(later).or_default() += 1;
+        after.entry(before).or_default().push(later);
+    }
+    let mut ready: Vec<usize> = incoming
+        .iter()
+        .filter_map(|(&variable_id, &count)| (count == 0).then_some(variable_id))
+        .collect();
+    let mut placed = 0;
+    while let Some(variable_id) = ready.pop() {
+        placed += 1;
+        for later in after.get(&variable_id).into_iter().flatten() {
+            let count = incoming.get_mut(later).expect("every variable is counted");
+            *count -= 1;
+            if *count == 0 {
+                ready.push(*later);
+            }
+        }
+    }
+    placed == incoming.len()
+}
+
+impl Display for Statistics {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let variables = |ids: &[usize]| {
+            if ids.is_empty() {
+                return "none".to_string();
+            }
+            ids.iter()
+                .map(|id| format!("x{id}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let path = |tests: &[(usize, bool)]| {
+            if tests.is_empty() {
+                return "none".to_string();
+            }
+            tests
+                .iter()
+                .map(|&(id, value)| {
+                    if value {
+                        format!("x{id}")
+                    } else {
+                        format!("!x{id}")
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+
+        writeln!(f, "Root: {}", self.root)?;
+        writeln!(f, "Variables: {}", self.variables)?;
+        writeln!(f, "Support: {}", variables(&self.support))?;
+        writeln!(f, "Unused: {}", variables(&self.unused))?;
+        writeln!(
+            f,
+            "Nodes: {} ({} unreachable)",
+            self.nodes, self.unreachable
+        )?;
+        let levels: Vec<String> = self
+            .level_sizes
+            .iter()
+            .enumerate()
+            .map(|(id, size)| format!("x{id}: {size}"))
+            .collect();
+        writeln!(f, "Nodes per variable: {}", levels.join(", "))?;
+        writeln!(f, "Depth: {}", self.depth)?;
+        writeln!(f, "Longest path: {}", path(&self.longest_path))?;
+        writeln!(f, "Shortest path: {}", path(&self.shortest_path))?;
+        writeln!(
+            f,
+            "Terminals: {} false, {} true",
+            self.terminals.0, self.terminals.1
+        )?;
+        writeln!(f, "Ordered: {}", yes_no(self.ordered))?;
+        write!(f, "Reduced: {}", yes_no(self.reduced))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::BinaryDecisionDiagram;
+
+    #[test]
+    fn canonical() {
+        // x0 xor x2, declared over three variables.
+        let bdd = BinaryDecisionDiagram::from_truth_table(&[
+            false, true, false, true, true, false, true, false,
+        ])
+        .unwrap();
+        let statistics = bdd.statistics();
+        assert_eq!(vec![1, 0, 2], statistics.level_sizes);
+        assert_eq!(vec![0, 2], statistics.support);
+        assert_eq!(vec![1], statistics.unused);
+        assert_eq!((2, 2), (statistics.depth, statistics.shortest_path.len()));
+        assert_eq!((1, 1), statistics.terminals);
+        assert_eq!(0, statistics.unreachable);
+        assert!(statistics.ordered && statistics.reduced);
+    }
+
+    #[test]
+    fn unordered_and_unreduced() {
+        let bdd: BinaryDecisionDiagram = "vars 2
+nodes 7
+root 0
+0 1 2 0
+1 3 4 1
+2 5 5 1
+5 3 4 0
+3 -1 -1 1
+4 -1 -1 0
+6 -1 -1 1"
+            .parse()
+            .unwrap();
+        let statistics = bdd.statistics();
+        assert_eq!(0, statistics.root);
+        assert_eq!(1, statistics.unreachable);
+        assert_eq!(3, statistics.depth);
+        assert_eq!(
+            vec![(0, false), (1, false), (0, false)],
+            statistics.longest_path
+        );
+        assert_eq!(vec![(0, true), (1, false)], statistics.shortest_path);
+        assert!(!statistics.ordered);
+        assert!(!statistics.reduced);
+    }
+
+    #[test]
+    fn constant() {
+        let statistics = BinaryDecisionDiagram::constant(false, 2).statistics();
+        assert_eq!(0, statistics.depth);
+        assert!(statistics.longest_path.is_empty());
+        assert_eq!(vec![0, 1], statistics.unused);
+        assert_eq!((1, 0), statistics.terminals);
+        assert!(statistics.to_string().contains("Support: none"));
+    }
+}
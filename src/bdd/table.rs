@@ -0,0 +1,162 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::bdd::builder::DiagramBuilder;
+use crate::bdd::BinaryDecisionDiagram;
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+impl BinaryDecisionDiagram {
+    /// Build a reduced ordered diagram from a truth table. Entry `i` is the
+    /// value of the function when variable `k` is bit `k` of `i`, the same
+    /// indexing [`crate::Evaluate::truth_table`] produces.
+    /// # Errors
+    /// * `ParseError` - the length of the table is not a power of two
+    /// # Example
+    /// ```
+    /// use flow::bdd::BinaryDecisionDiagram;
+    /// use flow::Evaluate;
+    /// let table = vec![false, true, true, false];
+    /// let bdd = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+    /// assert_eq!(table, bdd.truth_table().unwrap());
+    /// ```
+    pub fn from_truth_table(table: &[bool]) -> Result<Self, FlowError> {
+        if !table.len().is_power_of_two() {
+            return Err(ParseError("Truth table length is not a power of two"));
+        }
+        let variables = table.len().trailing_zeros() as usize;
+
+        let mut builder = DiagramBuilder::new();
+        let root = build(&mut builder, table, 0, 0, 1);
+        Ok(builder.build(root, variables))
+    }
+
+    /// Build a reduced ordered diagram from a truth table written in hex, most
+    /// significant digit first, so bit `i` of the number is entry `i` of the
+    /// table. An optional `0x` prefix is allowed. The function of two variables
+    /// `x0 & x1` is `8`.
+    /// # Errors
+    /// * `ParseError` - the string is not hex, has the wrong number of digits
+    ///   for `variables`, or sets bits beyond the table
+    pub fn from_hex_truth_table(hex: &str, variables: usize) -> Result<Self, FlowError> {
+        let hex = hex.trim();
+        let hex = hex.strip_prefix("0x").unwrap_or(hex);
+        let entries = u32::try_from(variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(ParseError("Too many variables for a truth table"))?;
+        if hex.len() != entries.div_ceil(4) {
+            return Err(ParseError("Hex truth table has the wrong number of digits"));
+        }
+
+        let mut table = Vec::with_capacity(hex.len() * 4);
+        for digit in hex.chars().rev() {
+            let digit = digit
+                .to_digit(16)
+                .ok_or(ParseError("Hex truth table contains a non-hex digit"))?;
+            table.extend((0..4).map(|bit| (digit >> bit) & 1 == 1));
+        }
+        if table[entries..].iter().any(|&bit| bit) {
+            return Err(ParseError("Hex truth table sets bits beyond the table"));
+        }
+        table.truncate(entries);
+        Self::from_truth_table(&table)
+    }
+}
+
+/// Build the sub-function over the entries `start + k * step`, which agree on
+/// every variable below `variable`.
+fn build(
+    builder: &mut DiagramBuilder,
+    table: &[bool],
+    variable: usize,
+    start: usize,
+    step: usize,
+) -> usize {
+    if step == table.len() {
+        return DiagramBuilder::constant(table[start]);
+    }
+    let low = build(builder, table, variable + 1, start, step * 2);
+    let high = build(builder, table, variable + 1, start + step, step * 2);
+    builder.mk(variable, low, high)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::Evaluate;
+
+    #[test]
+    fn round_trip() {
+        let table = vec![true, false, false, true, false, true, true, true];
+        let bdd = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+        assert_eq!(3, bdd.variables);
+        assert_eq!(table, bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn shares_subgraphs() {
+        let table: Vec<bool> = (0..16usize).map(|i| i.count_ones() % 2 == 1).collect();
+        let bdd = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+        // Parity needs two nodes per variable except the first, plus terminals.
+        assert_eq!(2 * 4 - 1 + 2, bdd.nodes.len());
+    }
+
+    #[test]
+    fn constant_table() {
+        let bdd = BinaryDecisionDiagram::from_truth_table(&[true; 4]).unwrap();
+        assert_eq!(vec![true; 4], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn length_not_power_of_two() {
+        assert!(BinaryDecisionDiagram::from_truth_table(&[true; 3]).is_err());
+        assert!(BinaryDecisionDiagram::from_truth_table(&[]).is_err());
+    }
+
+    #[test]
+    fn hex_and() {
+        let bdd = BinaryDecisionDiagram::from_hex_truth_table("0x8", 2).unwrap();
+        assert_eq!(vec![false, false, false, true], bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn hex_multiple_digits() {
+        let bdd = BinaryDecisionDiagram::from_hex_truth_table("e8", 3).unwrap();
+        let majority: Vec<bool> = (0..8usize).map(|i| i.count_ones() >= 2).collect();
+        assert_eq!(majority, bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn hex_single_variable() {
+        let bdd = BinaryDecisionDiagram::from_hex_truth_table("2", 1).unwrap();
+        assert_eq!(vec![false, true], bdd.truth_table().unwrap());
+        assert!(BinaryDecisionDiagram::from_hex_truth_table("4", 1).is_err());
+    }
+
+    #[test]
+    fn hex_no_variables() {
+        let bdd = BinaryDecisionDiagram::from_hex_truth_table("0x1", 0).unwrap();
+        assert_eq!(vec![true], bdd.truth_table().unwrap());
+        assert!(BinaryDecisionDiagram::from_hex_truth_table("2", 0).is_err());
+    }
+
+    #[test]
+    fn hex_wrong_length() {
+        assert!(BinaryDecisionDiagram::from_hex_truth_table("08", 2).is_err());
+        assert!(BinaryDecisionDiagram::from_hex_truth_table("g", 2).is_err());
+    }
+}
@@ -0,0 +1,314 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::bdd::BinaryNode::{Decision, Terminal};
+use crate::bdd::{BinaryDecisionDiagram, NodeIndex};
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+/// How the function of a diagram is spelled out in Verilog.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VerilogStyle {
+    /// one multiplexer wire per decision node, sharing subgraphs
+    MuxTree,
+    /// a single assign expression, with a wire for each node shared by
+    /// several parents so that the output stays linear in the diagram
+    Expression,
+}
+
+impl BinaryDecisionDiagram {
+    /// Write a synthesizable Verilog module computing this diagram. Variable
+    /// `k` becomes input port `x<k>` and the result is output port `f`.
+    /// # Errors
+    /// * `ParseError` - `module` is not a valid Verilog identifier
+    /// * `EvaluationError` - the diagram references a missing node or contains
+    ///   a cycle
+    /// # Example
+    /// ```
+    /// use flow::bdd::{BinaryDecisionDiagram, VerilogStyle};
+    /// let bdd = BinaryDecisionDiagram::variable(0, 1);
+    /// let verilog = bdd
+    ///     .to_verilog("identity", VerilogStyle::Expression)
+    ///     .unwrap();
+    /// assert!(verilog.contains("assign f = x0;"));
+    /// ```
+    pub fn to_verilog(&self, module: &str, style: VerilogStyle) -> Result<String, FlowError> {
+        if !is_identifier(module) {
+            return Err(ParseError("Module name is not a valid Verilog identifier"));
+        }
+        let order = self.topological_order()?;
+        let variables = order
+            .iter()
+            .filter_map(|id| match &self.nodes[id.0] {
+                Decision(node) => Some(node.variable_id + 1),
+                Terminal(_) => None,
+            })
+            .chain([self.variables])
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        let ports: Vec<String> = (0..variables)
+            .map(|variable| format!("    input wire x{variable},\n"))
+            .collect();
+        let _ = write!(
+            out,
+            "module {module} (\n{}    output wire f\n);\n",
+            ports.concat()
+        );
+
+        match style {
+            VerilogStyle::MuxTree => {
+                for id in &order {
+                    if let Decision(node) = &self.nodes[id.0] {
+                        let (low, high) = node.decision_map;
+                        let _ = writeln!(
+                            out,
+                            "    wire {} = x{} ? {} : {};",
+                            self.wire(*id),
+                            node.variable_id,
+                            self.wire(high),
+                            self.wire(low)
+                        );
+                    }
+                }
+                let _ = writeln!(out, "    assign f = {};", self.wire(self.entry_node));
+            },
+            VerilogStyle::Expression => {
+                let mut parents: HashMap<NodeIndex, usize> = HashMap::new();
+                for id in &order {
+                    if let Decision(node) = &self.nodes[id.0] {
+                        let (low, high) = node.decision_map;
+                        *parents.entry(low).or_default() += 1;
+                        *parents.entry(high).or_default() += 1;
+                    }
+                }
+                let shared: HashSet<NodeIndex> = order
+                    .iter()
+                    .filter(|id| {
+                        matches!(self.nodes[id.0], Decision(_))
+                            && parents.get(id).is_some_and(|&count| count > 1)
+                    })
+                    .copied()
+                    .collect();
+                for id in &order {
+                    if shared.contains(id) {
+                        let _ = write!(out, "    wire {} = ", self.wire(*id));
+                        self.write_expression(&mut out, *id, &shared);
+                        out.push_str(";\n");
+                    }
+                }
+                out.push_str("    assign f = ");
+                self.write_expression(&mut out, self.entry_node, &shared);
+                out.push_str(";\n");
+            },
+        }
+        out.push_str("endmodule\n");
+        Ok(out)
+    }
+
+    /// The name of the wire carrying the value of a node.
+    fn wire(&self, id: NodeIndex) -> String {
+        match &self.nodes[id.0] {
+            Terminal(value) => constant(*value).to_string(),
+            Decision(_) => format!("n{}", self.node_ids[id.0]),
+        }
+    }
+
+    /// Write the expression for `root`, inlining every node below it except
+    /// the `shared` ones, which are referred to by wire. Multiplexers fold
+    /// into plain gates when an input is constant. The expression is written
+    /// front to back from an explicit stack, so its length bounds the work.
+    fn write_expression(&self, out: &mut String, root: NodeIndex, shared: &HashSet<NodeIndex>) {
+        /// Text still to be written, or a node whose expression goes there.
+        enum Piece {
+            Text(&'static str),
+            Node(NodeIndex),
+        }
+        let mut stack = vec![Piece::Node(root)];
+        while let Some(piece) = stack.pop() {
+            let id = match piece {
+                Piece::Text(text) => {
+                    out.push_str(text);
+                    continue;
+                },
+                Piece::Node(id) => id,
+            };
+            let node = match &self.nodes[id.0] {
+                Decision(node) if id == root || !shared.contains(&id) => node,
+                _ => {
+                    out.push_str(&self.wire(id));
+                    continue;
+                },
+            };
+            let (low, high) = node.decision_map;
+            let (prefix, pieces) = match (self.terminal(high), self.terminal(low)) {
+                (Some(true), Some(false)) => ("", vec![]),
+                (Some(false), Some(true)) => ("~", vec![]),
+                (_, Some(false)) => ("(", vec![Piece::Text(" & "), Piece::Node(high)]),
+                (Some(true), _) => ("(", vec![Piece::Text(" | "), Piece::Node(low)]),
+                (Some(false), _) => ("(~", vec![Piece::Text(" & "), Piece::Node(low)]),
+                (_, Some(true)) => ("(~", vec![Piece::Text(" | "), Piece::Node(high)]),
+                (None, None) => (
+                    "(",
+                    vec![
+                        Piece::Text(" ? "),
+                        Piece::Node(high),
+                        Piece::Text(" : "),
+                        Piece::Node(low),
+                    ],
+                ),
+            };
+            let _ = write!(out, "{prefix}x{}", node.variable_id);
+            if !pieces.is_empty() {
+                stack.push(Piece::Text(")"));
+                stack.extend(pieces.into_iter().rev());
+            }
+        }
+    }
+
+    /// The value of a node if it is a terminal.
+    fn terminal(&self, id: NodeIndex) -> Option<bool> {
+        match self.nodes[id.0] {
+            Terminal(value) => Some(value),
+            Decision(_) => None,
+        }
+    }
+}
+
+fn constant(value: bool) -> &'static str {
+    if value {
+        "1'b1"
+    } else {
+        "1'b0"
+    }
+}
+
+/// Whether `name` can be used as a Verilog module name.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::builder::{and_chain, DEEP};
+    use crate::bdd::{BinaryDecisionDiagram, BinaryOperator, VerilogStyle};
+
+    const XOR_BDD: &str = "vars 2
+nodes 5
+0 2 1 0
+1 4 3 1
+2 3 4 1
+3 -1 -1 0
+4 -1 -1 1";
+
+    #[test]
+    fn mux_tree() {
+        let bdd: BinaryDecisionDiagram = XOR_BDD.parse().unwrap();
+        let verilog = bdd.to_verilog("xor2", VerilogStyle::MuxTree).unwrap();
+        assert_eq!(
+            "module xor2 (
+    input wire x0,
+    input wire x1,
+    output wire f
+);
+    wire n2 = x1 ? 1'b0 : 1'b1;
+    wire n1 = x1 ? 1'b1 : 1'b0;
+    wire n0 = x0 ? n2 : n1;
+    assign f = n0;
+endmodule
+",
+            verilog
+        );
+    }
+
+    #[test]
+    fn expression() {
+        let bdd: BinaryDecisionDiagram = XOR_BDD.parse().unwrap();
+        let verilog = bdd.to_verilog("xor2", VerilogStyle::Expression).unwrap();
+        assert!(verilog.contains("    assign f = (x0 ? ~x1 : x1);\n"));
+    }
+
+    #[test]
+    fn expression_shares_nodes() {
+        let parity = (1..64).fold(BinaryDecisionDiagram::variable(0, 64), |acc, variable| {
+            acc.apply(
+                &BinaryDecisionDiagram::variable(variable, 64),
+                BinaryOperator::Xor,
+            )
+            .unwrap()
+        });
+        let verilog = parity
+            .to_verilog("parity", VerilogStyle::Expression)
+            .unwrap();
+        // Both nodes of every level below the second are shared by the two
+        // nodes above them.
+        assert_eq!(2 * 62, verilog.matches("    wire n").count());
+        assert!(verilog.len() < 100 * parity.nodes.len());
+    }
+
+    #[test]
+    fn expression_names_shared_nodes() {
+        let bdd: BinaryDecisionDiagram = "vars 3
+nodes 7
+0 1 2 0
+1 3 4 1
+2 4 3 1
+3 5 6 2
+4 6 5 2
+5 -1 -1 1
+6 -1 -1 0"
+            .parse()
+            .unwrap();
+        let verilog = bdd.to_verilog("parity3", VerilogStyle::Expression).unwrap();
+        assert!(verilog.contains(
+            "    wire n3 = x2;
+    wire n4 = ~x2;
+    assign f = (x0 ? (x1 ? n3 : n4) : (x1 ? n4 : n3));
+"
+        ));
+    }
+
+    #[test]
+    fn expression_of_deep_chain() {
+        let verilog = and_chain(DEEP)
+            .to_verilog("chain", VerilogStyle::Expression)
+            .unwrap();
+        assert!(verilog.contains("    assign f = (x0 & (x1 & (x2 & "));
+        assert_eq!(DEEP - 1, verilog.matches(" & ").count());
+        assert!(!verilog.contains("    wire"));
+    }
+
+    #[test]
+    fn constant_function() {
+        let bdd = BinaryDecisionDiagram::constant(false, 1);
+        let verilog = bdd.to_verilog("zero", VerilogStyle::MuxTree).unwrap();
+        assert!(verilog.contains("    assign f = 1'b0;\n"));
+    }
+
+    #[test]
+    fn invalid_module_name() {
+        let bdd = BinaryDecisionDiagram::variable(0, 1);
+        assert!(bdd.to_verilog("1bad", VerilogStyle::MuxTree).is_err());
+    }
+}
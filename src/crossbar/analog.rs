@@ -0,0 +1,458 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::crossbar::{CrossbarMatrix, Wire};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{convert_bits_to_bools, FlowError};
+
+/// Residual, relative to the applied current, at which the solver stops.
+const TOLERANCE: f64 = 1e-12;
+/// Most variables [`CrossbarMatrix::noise_margins`] enumerates, one solve per
+/// assignment.
+pub const MAX_MARGIN_VARIABLES: usize = 20;
+
+/// Electrical parameters for [`CrossbarMatrix::simulate`]. Resistances are
+/// in ohms and the voltage in volts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnalogOptions {
+    /// resistance of a device that conducts
+    pub r_on: f64,
+    /// resistance of a device that does not conduct, including devices that
+    /// are permanently off
+    pub r_off: f64,
+    /// resistance from each output wire to ground, across which the output is
+    /// sensed
+    pub r_sense: f64,
+    /// voltage applied to the input wire
+    pub voltage: f64,
+}
+
+impl Default for AnalogOptions {
+    fn default() -> Self {
+        Self {
+            r_on: 1e3,
+            r_off: 1e6,
+            r_sense: 1e3,
+            voltage: 1.0,
+        }
+    }
+}
+
+/// What is measured at one output wire.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OutputReading {
+    /// voltage across the sense resistor
+    pub voltage: f64,
+    /// current through the sense resistor
+    pub current: f64,
+}
+
+/// How well the sensed voltages of one output separate true from false
+/// across every assignment of the variables.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoiseMargin {
+    /// the lowest voltage of an assignment the output is true for
+    pub lowest_true: Option<f64>,
+    /// the highest voltage of an assignment the output is false for
+    pub highest_false: Option<f64>,
+}
+
+impl NoiseMargin {
+    /// `lowest_true - highest_false`, or `None` when the output is constant.
+    /// A threshold between the two reads the output correctly when this is
+    /// positive.
+    #[must_use]
+    pub fn margin(&self) -> Option<f64> { Some(self.lowest_true? - self.highest_false?) }
+}
+
+impl AnalogOptions {
+    /// Reject parameters the solver cannot use.
+    pub(crate) fn validate(&self) -> Result<(), FlowError> {
+        if ![self.r_on, self.r_off, self.r_sense]
+            .iter()
+            .all(|r| r.is_finite() && *r > 0.0)
+        {
+            return Err(EvaluationError("Resistances must be positive and finite"));
+        }
+        if !(self.voltage.is_finite() && self.voltage > 0.0) {
+            return Err(EvaluationError("Voltage must be positive and finite"));
+        }
+        Ok(())
+    }
+}
+
+impl CrossbarMatrix {
+    /// Simulate the crossbar as a resistive network: the input wire is held
+    /// at `options.voltage`, every output wire is tied to ground through
+    /// `options.r_sense` and every crossing is a resistor of `options.r_on` or
+    /// `options.r_off`. The wire voltages come from the nodal equations,
+    /// solved with a Jacobi-preconditioned conjugate gradient that never forms
+    /// the matrix.
+    /// # Errors
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    /// * `EvaluationError` - a resistance or the voltage is not positive and
+    ///   finite, or the solver did not converge
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{AnalogOptions, CrossbarMatrix, Literal, Wire};
+    /// let mut crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Column(0)]).unwrap();
+    /// crossbar.set_cell(0, 0, Literal::On);
+    /// let reading = crossbar.simulate(&[], &AnalogOptions::default()).unwrap()[0];
+    /// assert!((reading.voltage - 0.5).abs() < 1e-9);
+    /// ```
+    pub fn simulate(
+        &self,
+        values: &[bool],
+        options: &AnalogOptions,
+    ) -> Result<Vec<OutputReading>, FlowError> {
+        options.validate()?;
+        let AnalogOptions {
+            r_on,
+            r_off,
+            r_sense,
+            voltage,
+        } = *options;
+        if values.len() < self.variables() {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let conductances: Vec<f64> = self
+            .cells
+            .iter()
+            .map(|literal| {
+                if literal.conducts(values) {
+                    1.0 / r_on
+                } else {
+                    1.0 / r_off
+                }
+            })
+            .collect();
+        let network = Network {
+            crossbar: self,
+            conductances,
+            sense: 1.0 / r_sense,
+        };
+        let voltages = network.solve(voltage)?;
+        Ok(self
+            .outputs
+            .iter()
+            .map(|&output| {
+                let voltage = voltages[network.vertex(output)];
+                OutputReading {
+                    voltage,
+                    current: voltage / r_sense,
+                }
+            })
+            .collect())
+    }
+
+    /// The [`NoiseMargin`] of each output, simulating every assignment of the
+    /// variables and reading it as true when current reaches the output.
+    /// # Errors
+    /// * `EvaluationError` - as for [`CrossbarMatrix::simulate`], or the
+    ///   crossbar uses more than [`MAX_MARGIN_VARIABLES`] variables
+    pub fn noise_margins(&self, options: &AnalogOptions) -> Result<Vec<NoiseMargin>, FlowError> {
+        options.validate()?;
+        let variables = self.variables();
+        if variables > MAX_MARGIN_VARIABLES {
+            return Err(EvaluationError(
+                "Too many variables to simulate every assignment for noise margins",
+            ));
+        }
+        let combinations = 1 << variables;
+        let mut margins = vec![
+            NoiseMargin {
+                lowest_true: None,
+                highest_false: None,
+            };
+            self.outputs.len()
+        ];
+        for var_set in 0..combinations {
+            let values = convert_bits_to_bools(var_set, variables);
+            let expected = self.eval_outputs(&values)?;
+            let readings = self.simulate(&values, options)?;
+            for ((margin, value), reading) in margins.iter_mut().zip(expected).zip(readings) {
+                if value {
+                    margin.lowest_true = Some(
+                        margin
+                            .lowest_true
+                            .map_or(reading.voltage, |v| v.min(reading.voltage)),
+                    );
+                } else {
+                    margin.highest_false = Some(
+                        margin
+                            .highest_false
+                            .map_or(reading.voltage, |v| v.max(reading.voltage)),
+                    );
+                }
+            }
+        }
+        Ok(margins)
+    }
+}
+
+/// The conductance matrix of a crossbar under one assignment. Wires are
+/// vertices numbered rows first; the input is a fixed voltage rather than
+/// an unknown, so its entry in every vector is ignored.
+struct Network<'a> {
+    crossbar: &'a CrossbarMatrix,
+    /// Conductance of each crossing, row-major.
+    conductances: Vec<f64>,
+    /// Conductance from an output wire to ground.
+    sense: f64,
+}
+
+impl Network<'_> {
+    fn vertex(&self, wire: Wire) -> usize {
+        match wire {
+            Wire::Row(row) => row,
+            Wire::Column(column) => self.crossbar.rows + column,
+        }
+    }
+
+    fn conductance(&self, row: usize, column: usize) -> f64 {
+        self.conductances[row * self.crossbar.columns + column]
+    }
+
+    /// Multiply the matrix, whose diagonal is `diagonal`, by `x` with the
+    /// input's entry taken as zero.
+    fn apply(&self, x: &[f64], input: usize, diagonal: &[f64]) -> Vec<f64> {
+        let rows = self.crossbar.rows;
+        let value = |vertex: usize| if vertex == input { 0.0 } else { x[vertex] };
+        let mut product: Vec<f64> = (0..x.len())
+            .map(|vertex| diagonal[vertex] * value(vertex))
+            .collect();
+        for row in 0..rows {
+            for column in 0..self.crossbar.columns {
+                let g = self.conductance(row, column);
+                product[row] -= g * value(rows + column);
+                product[rows + column] -= g * value(row);
+            }
+        }
+        product[input] = 0.0;
+        product
+    }
+
+    fn diagonal(&self, vertex: usize) -> f64 {
+        let rows = self.crossbar.rows;
+        let through_devices: f64 = if vertex < rows {
+            (0..self.crossbar.columns)
+                .map(|column| self.conductance(vertex, column))
+                .sum()
+        } else {
+            (0..rows)
+                .map(|row| self.conductance(row, vertex - rows))
+                .sum()
+        };
+        let sensed = self
+            .crossbar
+            .outputs
+            .iter()
+            .any(|&output| self.vertex(output) == vertex);
+        through_devices + if sensed { self.sense } else { 0.0 }
+    }
+
+    /// The voltage of every wire with the input held at `voltage`.
+    fn solve(&self, voltage: f64) -> Result<Vec<f64>, FlowError> {
+        let size = self.crossbar.rows + self.crossbar.columns;
+        let input = self.vertex(self.crossbar.input);
+        // Current pushed into each wire by its device to the input.
+        let mut rhs = vec![0.0; size];
+        match self.crossbar.input {
+            Wire::Row(row) => {
+                for column in 0..self.crossbar.columns {
+                    rhs[self.crossbar.rows + column] = self.conductance(row, column) * voltage;
+                }
+            },
+            Wire::Column(column) => {
+                for (row, entry) in rhs.iter_mut().enumerate().take(self.crossbar.rows) {
+                    *entry = self.conductance(row, column) * voltage;
+                }
+            },
+        }
+        let diagonal: Vec<f64> = (0..size).map(|vertex| self.diagonal(vertex)).collect();
+        let inverse_diagonal: Vec<f64> = (0..size)
+            .map(|vertex| {
+                if vertex == input {
+                    0.0
+                } else {
+                    1.0 / diagonal[vertex]
+                }
+            })
+            .collect();
+        let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(a, b)| a * b).sum::<f64>();
+        let scale = dot(&rhs, &rhs).sqrt();
+
+        let mut x = vec![0.0; size];
+        let mut residual = rhs;
+        let mut z: Vec<f64> = residual
+            .iter()
+            .zip(&inverse_diagonal)
+            .map(|(r, d)| r * d)
+            .collect();
+        let mut direction = z.clone();
+        let mut rz = dot(&residual, &z);
+        for _ in 0..=4 * size + 100 {
+            if dot(&residual, &residual).sqrt() <= TOLERANCE * scale {
+                x[input] = voltage;
+                return Ok(x);
+            }
+            let product = self.apply(&direction, input, &diagonal);
+            let step = rz / dot(&direction, &product);
+            for vertex in 0..size {
+                x[vertex] += step * direction[vertex];
+                residual[vertex] -= step * product[vertex];
+            }
+            z = residual
+                .iter()
+                .zip(&inverse_diagonal)
+                .map(|(r, d)| r * d)
+                .collect();
+            let next = dot(&residual, &z);
+            for vertex in 0..size {
+                direction[vertex] = z[vertex] + next / rz * direction[vertex];
+            }
+            rz = next;
+        }
+        Err(EvaluationError("The nodal equations did not converge"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{AnalogOptions, CrossbarMatrix, Literal, Wire, MAX_MARGIN_VARIABLES};
+    use crate::gen::{random_crossbar, CrossbarOptions};
+
+    /// x0 xor x1, as in the evaluation tests.
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0
+!x1 x1
+0   0
+x0  !x0";
+
+    fn close(expected: f64, actual: f64) -> bool { (expected - actual).abs() < 1e-9 }
+
+    #[test]
+    fn divider() {
+        let mut crossbar = CrossbarMatrix::new(2, 1, Wire::Row(1), vec![Wire::Row(0)]).unwrap();
+        crossbar.set_cell(0, 0, Literal::On);
+        crossbar.set_cell(1, 0, Literal::On);
+        let options = AnalogOptions {
+            r_on: 100.0,
+            r_off: 1e9,
+            r_sense: 800.0,
+            voltage: 2.0,
+        };
+        let reading = crossbar.simulate(&[], &options).unwrap()[0];
+        // Two devices in series with the sense resistor.
+        assert!(close(2.0 * 800.0 / 1000.0, reading.voltage));
+        assert!(close(reading.voltage / 800.0, reading.current));
+    }
+
+    #[test]
+    fn off_devices_leak() {
+        let crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Column(0)]).unwrap();
+        let options = AnalogOptions {
+            r_off: 9e3,
+            ..AnalogOptions::default()
+        };
+        let reading = crossbar.simulate(&[], &options).unwrap()[0];
+        assert!(close(0.1, reading.voltage));
+    }
+
+    #[test]
+    fn margins() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let good = crossbar.noise_margins(&AnalogOptions::default()).unwrap()[0];
+        assert!(good.margin().unwrap() > 0.0);
+        let poor = crossbar
+            .noise_margins(&AnalogOptions {
+                r_off: 2e3,
+                ..AnalogOptions::default()
+            })
+            .unwrap()[0];
+        assert!(poor.margin().unwrap() < good.margin().unwrap());
+    }
+
+    #[test]
+    fn random_crossbars_separate() {
+        for seed in 0..8 {
+            let crossbar = random_crossbar(&CrossbarOptions {
+                rows: 12,
+                columns: 12,
+                variables: 5,
+                density: 0.3,
+                seed,
+            })
+            .unwrap();
+            let values = [true, false, true, true, false];
+            let expected = crossbar.eval_outputs(&values).unwrap()[0];
+            let reading = crossbar
+                .simulate(&values, &AnalogOptions::default())
+                .unwrap()[0];
+            assert!(reading.voltage > 0.0 && reading.voltage < 1.0);
+            assert_eq!(expected, reading.voltage > 0.05, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn constant_output() {
+        let crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Row(0)]).unwrap();
+        let margin = crossbar.noise_margins(&AnalogOptions::default()).unwrap()[0];
+        assert!(close(1.0, margin.lowest_true.unwrap()));
+        assert_eq!(None, margin.margin());
+    }
+
+    #[test]
+    fn too_many_variables() {
+        let mut crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Column(0)]).unwrap();
+        crossbar.set_cell(0, 0, Literal::Positive(MAX_MARGIN_VARIABLES));
+        assert!(crossbar.noise_margins(&AnalogOptions::default()).is_err());
+        assert!(crossbar
+            .simulate(&[true; MAX_MARGIN_VARIABLES + 1], &AnalogOptions::default())
+            .is_ok());
+    }
+
+    #[test]
+    fn invalid_resistance() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let options = AnalogOptions {
+            r_on: 0.0,
+            ..AnalogOptions::default()
+        };
+        assert!(crossbar.simulate(&[false, false], &options).is_err());
+        assert!(crossbar
+            .simulate(&[false], &AnalogOptions::default())
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_voltage() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        for voltage in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let options = AnalogOptions {
+                voltage,
+                ..AnalogOptions::default()
+            };
+            assert!(crossbar.simulate(&[false, false], &options).is_err());
+            assert!(crossbar.noise_margins(&options).is_err());
+            assert!(crossbar.to_spice(None, &options).is_err());
+        }
+    }
+}
@@ -0,0 +1,510 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::crossbar::parse::Lines;
+use crate::crossbar::{CrossbarMatrix, Literal, LocatedError, Wire};
+use crate::gen::Rng;
+use crate::FlowError::EvaluationError;
+use crate::{convert_bits_to_bools, FlowError};
+
+/// How many column orders the mapper tries before giving up.
+const ATTEMPTS: usize = 64;
+
+/// A fabrication fault that fixes a device regardless of how it is
+/// programmed.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Defect {
+    /// always conducts
+    StuckOn,
+    /// never conducts
+    StuckOff,
+}
+
+/// The defective cells of a physical crossbar.
+///
+/// The text format is one line per row of whitespace-separated cells: `.`
+/// for a working device, `1` for stuck on and `0` for stuck off. Text after
+/// `#` is a comment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DefectMap {
+    rows: usize,
+    columns: usize,
+    /// Cells in row-major order.
+    cells: Vec<Option<Defect>>,
+}
+
+impl DefectMap {
+    /// A crossbar with no defects.
+    /// # Errors
+    /// * `ParseError` - the crossbar has no rows or columns
+    pub fn new(rows: usize, columns: usize) -> Result<Self, FlowError> {
+        if rows == 0 || columns == 0 {
+            return Err(FlowError::ParseError(
+                "A crossbar needs at least one row and column",
+            ));
+        }
+        Ok(Self {
+            rows,
+            columns,
+            cells: vec![None; rows * columns],
+        })
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize { self.rows }
+
+    #[must_use]
+    pub fn columns(&self) -> usize { self.columns }
+
+    /// The defect at a crossing, if any.
+    /// # Panics
+    /// The crossing is outside the crossbar.
+    #[must_use]
+    pub fn get(&self, row: usize, column: usize) -> Option<Defect> {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Cell is outside the crossbar"
+        );
+        self.cells[row * self.columns + column]
+    }
+
+    /// # Panics
+    /// The crossing is outside the crossbar.
+    pub fn set(&mut self, row: usize, column: usize, defect: Option<Defect>) {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Cell is outside the crossbar"
+        );
+        self.cells[row * self.columns + column] = defect;
+    }
+
+    /// The number of defective cells.
+    #[must_use]
+    pub fn count(&self) -> usize { self.cells.iter().flatten().count() }
+
+    /// Read the defect map text format.
+    /// # Errors
+    /// * `LocatedError` - the line and column the text stops making sense at
+    pub fn parse(s: &str) -> Result<Self, LocatedError> {
+        let mut lines = Lines::new(s);
+        let mut cells = Vec::new();
+        let mut columns = None;
+        let mut rows = 0;
+        while let Some((line, tokens)) = lines.next() {
+            let &(column, word) = tokens.last().unwrap_or(&(1, ""));
+            match columns {
+                None => columns = Some(tokens.len()),
+                Some(count) if count < tokens.len() => {
+                    return Err(LocatedError {
+                        line,
+                        column: tokens[count].0,
+                        message: "Row has more cells than the first row",
+                    })
+                },
+                Some(count) if count > tokens.len() => {
+                    return Err(LocatedError {
+                        line,
+                        column: column + word.len(),
+                        message: "Row has fewer cells than the first row",
+                    })
+                },
+                Some(_) => {},
+            }
+            for (column, word) in tokens {
+                cells.push(match word {
+                    "." => None,
+                    "1" => Some(Defect::StuckOn),
+                    "0" => Some(Defect::StuckOff),
+                    _ => {
+                        return Err(LocatedError {
+                            line,
+                            column,
+                            message: "Cell must be ., 0 or 1",
+                        })
+                    },
+                });
+            }
+            rows += 1;
+        }
+        let columns = columns.ok_or(LocatedError {
+            line: s.lines().count() + 1,
+            column: 1,
+            message: "Defect map has no rows",
+        })?;
+        Ok(Self {
+            rows,
+            columns,
+            cells,
+        })
+    }
+}
+
+impl FromStr for DefectMap {
+    type Err = FlowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Ok(Self::parse(s)?) }
+}
+
+impl Display for DefectMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for row in self.cells.chunks(self.columns) {
+            let cells: Vec<&str> = row
+                .iter()
+                .map(|cell| match cell {
+                    None => ".",
+                    Some(Defect::StuckOn) => "1",
+                    Some(Defect::StuckOff) => "0",
+                })
+                .collect();
+            writeln!(f, "{}", cells.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// An assignment under which defects change at least one output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DefectChange {
+    pub values: Vec<bool>,
+    /// the outputs of the crossbar as programmed
+    pub expected: Vec<bool>,
+    /// the outputs with the defects in place
+    pub actual: Vec<bool>,
+}
+
+/// Where a logical crossbar was placed on a defective one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mapping {
+    /// the physical row of each logical row
+    pub rows: Vec<usize>,
+    /// the physical column of each logical column
+    pub columns: Vec<usize>,
+    /// the physical crossbar as it is programmed
+    pub crossbar: CrossbarMatrix,
+}
+
+impl CrossbarMatrix {
+    /// The crossbar as it behaves with the defects in place: stuck-on cells
+    /// always conduct and stuck-off cells never do.
+    /// # Errors
+    /// * `EvaluationError` - the defect map is not the size of the crossbar
+    pub fn with_defects(&self, defects: &DefectMap) -> Result<Self, FlowError> {
+        if (defects.rows, defects.columns) != (self.rows, self.columns) {
+            return Err(EvaluationError(
+                "The defect map is not the size of the crossbar",
+            ));
+        }
+        let mut crossbar = self.clone();
+        for (cell, defect) in crossbar.cells.iter_mut().zip(&defects.cells) {
+            match defect {
+                Some(Defect::StuckOn) => *cell = Literal::On,
+                Some(Defect::StuckOff) => *cell = Literal::Off,
+                None => {},
+            }
+        }
+        Ok(crossbar)
+    }
+
+    /// Every row of the truth table the defects change, in truth table order.
+    /// # Errors
+    /// * `EvaluationError` - the defect map is not the size of the crossbar, or
+    ///   there are too many variables to enumerate
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{CrossbarMatrix, Defect, DefectMap};
+    /// let crossbar: CrossbarMatrix = "rows 2\ncolumns 1\ninput r1\noutputs r0\nx0\nx1"
+    ///     .parse()
+    ///     .unwrap();
+    /// let mut defects = DefectMap::new(2, 1).unwrap();
+    /// defects.set(1, 0, Some(Defect::StuckOn));
+    /// // x0 and x1 becomes just x0.
+    /// let changes = crossbar.defect_changes(&defects).unwrap();
+    /// assert_eq!(1, changes.len());
+    /// assert_eq!(vec![true, false], changes[0].values);
+    /// assert_eq!(vec![true], changes[0].actual);
+    /// ```
+    pub fn defect_changes(&self, defects: &DefectMap) -> Result<Vec<DefectChange>, FlowError> {
+        let defective = self.with_defects(defects)?;
+        let variables = self.variables();
+        let combinations = u32::try_from(variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
+        let mut changes = Vec::new();
+        for var_set in 0..combinations {
+            let values = convert_bits_to_bools(var_set, variables);
+            let expected = self.eval_outputs(&values)?;
+            let actual = defective.eval_outputs(&values)?;
+            if expected != actual {
+                changes.push(DefectChange {
+                    values,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(changes)
+    }
+
+    /// Place the crossbar onto a physical crossbar with defects, permuting
+    /// rows and columns so that no device lands on a cell whose defect would
+    /// change it: a literal needs a working cell, an always-on device a cell
+    /// that is not stuck off, and an unused crossing a cell that is not stuck
+    /// on. Unused physical wires are left off.
+    ///
+    /// Columns are tried in order of fewest defects and then in seeded random
+    /// orders, with the rows matched to each by bipartite matching. A
+    /// placement is only accepted once it evaluates like the original with
+    /// the defects in place, which also catches stuck-on cells joining wires
+    /// through the unused ones.
+    /// # Errors
+    /// * `EvaluationError` - the physical crossbar is too small, no placement
+    ///   was found, or there are too many variables to check one
+    pub fn map_onto(&self, defects: &DefectMap) -> Result<Mapping, FlowError> {
+        if defects.rows < self.rows || defects.columns < self.columns {
+            return Err(EvaluationError(
+                "The defective crossbar is smaller than the crossbar",
+            ));
+        }
+        let fits = |literal: Literal, defect: Option<Defect>| {
+            matches!(
+                (literal, defect),
+                (_, None)
+                    | (Literal::Off, Some(Defect::StuckOff))
+                    | (Literal::On, Some(Defect::StuckOn))
+            )
+        };
+
+        let mut order: Vec<usize> = (0..defects.columns).collect();
+        order.sort_by_key(|&column| {
+            (0..defects.rows)
+                .filter(|&row| defects.get(row, column).is_some())
+                .count()
+        });
+        let mut rng = Rng::new(0);
+        for attempt in 0..ATTEMPTS {
+            if attempt > 0 {
+                rng.shuffle(&mut order);
+            }
+            let columns = &order[..self.columns];
+            let Some(rows) = matching(self.rows, defects.rows, |row, physical| {
+                (0..self.columns).all(|column| {
+                    fits(
+                        self.cell(row, column),
+                        defects.get(physical, columns[column]),
+                    )
+                })
+            }) else {
+                continue;
+            };
+            let crossbar = self.placed(defects, &rows, columns)?;
+            if crossbar.with_defects(defects)?.equivalent(self)? {
+                return Ok(Mapping {
+                    rows,
+                    columns: columns.to_vec(),
+                    crossbar,
+                });
+            }
+        }
+        Err(EvaluationError(
+            "No placement keeps the devices off the defective cells",
+        ))
+    }
+
+    /// The crossbar programmed onto the physical rows and columns given.
+    fn placed(
+        &self,
+        defects: &DefectMap,
+        rows: &[usize],
+        columns: &[usize],
+    ) -> Result<Self, FlowError> {
+        let wire = |wire| match wire {
+            Wire::Row(row) => Wire::Row(rows[row]),
+            Wire::Column(column) => Wire::Column(columns[column]),
+        };
+        let mut crossbar = Self::new(
+            defects.rows,
+            defects.columns,
+            wire(self.input),
+            self.outputs.iter().map(|&output| wire(output)).collect(),
+        )?;
+        for (row, &physical_row) in rows.iter().enumerate() {
+            for (column, &physical_column) in columns.iter().enumerate() {
+                crossbar.set_cell(physical_row, physical_column, self.cell(row, column));
+            }
+        }
+        Ok(crossbar)
+    }
+}
+
+/// A match of every left vertex to a distinct right vertex it fits, found by
+/// augmenting paths, or `None` when there is no such match.
+fn matching(left: usize, right: usize, fits: impl Fn(usize, usize) -> bool) -> Option<Vec<usize>> {
+    fn augment(
+        vertex: usize,
+        allowed: &[Vec<usize>],
+        owners: &mut [Option<usize>],
+        seen: &mut [bool],
+    ) -> bool {
+        for &candidate in &allowed[vertex] {
+            if seen[candidate] {
+                continue;
+            }
+            seen[candidate] = true;
+            let free = match owners[candidate] {
+                None => true,
+                Some(owner) => augment(owner, allowed, owners, seen),
+            };
+            if free {
+                owners[candidate] = Some(vertex);
+                return true;
+            }
+        }
+        false
+    }
+
+    let allowed: Vec<Vec<usize>> = (0..left)
+        .map(|vertex| {
+            (0..right)
+                .filter(|&candidate| fits(vertex, candidate))
+                .collect()
+        })
+        .collect();
+    let mut owners = vec![None; right];
+    for vertex in 0..left {
+        if !augment(vertex, &allowed, &mut owners, &mut vec![false; right]) {
+            return None;
+        }
+    }
+    let mut matched = vec![0; left];
+    for (candidate, owner) in owners.iter().enumerate() {
+        if let Some(vertex) = owner {
+            matched[*vertex] = candidate;
+        }
+    }
+    Some(matched)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Defect, DefectMap, Wire};
+    use crate::gen::{random_crossbar, random_defects, CrossbarOptions, DefectOptions};
+
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn parse() {
+        let defects: DefectMap = ". 1 .\n0 . .  # comment\n".parse().unwrap();
+        assert_eq!((2, 3), (defects.rows(), defects.columns()));
+        assert_eq!(Some(Defect::StuckOn), defects.get(0, 1));
+        assert_eq!(Some(Defect::StuckOff), defects.get(1, 0));
+        assert_eq!(2, defects.count());
+        assert_eq!(". 1 .\n0 . .\n", defects.to_string());
+        let error = DefectMap::parse(". .\n. x").unwrap_err();
+        assert_eq!((2, 3), (error.line, error.column));
+        assert!(DefectMap::parse(". .\n.").is_err());
+        assert!(DefectMap::parse("# nothing").is_err());
+    }
+
+    #[test]
+    fn changes() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let defects = DefectMap::new(3, 2).unwrap();
+        assert_eq!(Ok(vec![]), crossbar.defect_changes(&defects));
+
+        let defects: DefectMap = ". .\n. .\n0 .".parse().unwrap();
+        let changes = crossbar.defect_changes(&defects).unwrap();
+        // Only x0 and not x1 used the stuck-off device.
+        assert_eq!(1, changes.len());
+        assert_eq!(vec![true, false], changes[0].values);
+        assert_eq!(vec![true, false], changes[0].expected);
+        assert_eq!(vec![false, false], changes[0].actual);
+        assert!(crossbar
+            .defect_changes(&DefectMap::new(2, 2).unwrap())
+            .is_err());
+    }
+
+    #[test]
+    fn map_around_defects() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let defects: DefectMap = "0 . 1\n. . .\n. 1 .\n. . 0".parse().unwrap();
+        let mapping = crossbar.map_onto(&defects).unwrap();
+        assert_eq!(Wire::Row(mapping.rows[2]), mapping.crossbar.input());
+        assert_eq!(Ok(vec![]), mapping.crossbar.defect_changes(&defects));
+        assert!(crossbar
+            .equivalent(&mapping.crossbar.with_defects(&defects).unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn unmappable() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        // Every row has a stuck-on cell where XOR needs a literal.
+        let defects: DefectMap = "1 1\n1 1\n1 1".parse().unwrap();
+        assert!(crossbar.map_onto(&defects).is_err());
+        assert!(crossbar.map_onto(&DefectMap::new(2, 2).unwrap()).is_err());
+    }
+
+    #[test]
+    fn unused_wires() {
+        // Row 1 is spare, but stuck on in both columns it would join.
+        let crossbar: CrossbarMatrix = "rows 2\ncolumns 2\ninput r1\noutputs r0\nx0 0\n0  x1"
+            .parse()
+            .unwrap();
+        let defects: DefectMap = ". . .\n1 . 1\n. . .".parse().unwrap();
+        let mapping = crossbar.map_onto(&defects).unwrap();
+        assert!(crossbar
+            .equivalent(&mapping.crossbar.with_defects(&defects).unwrap())
+            .unwrap());
+    }
+
+    #[test]
+    fn random_mapping() {
+        let mut mapped = 0;
+        for seed in 0..20 {
+            let crossbar = random_crossbar(&CrossbarOptions {
+                rows: 4,
+                columns: 4,
+                variables: 3,
+                density: 0.4,
+                seed,
+            })
+            .unwrap();
+            let defects = random_defects(&DefectOptions {
+                rows: 6,
+                columns: 6,
+                stuck_on: 0.05,
+                stuck_off: 0.05,
+                seed,
+            })
+            .unwrap();
+            if let Ok(mapping) = crossbar.map_onto(&defects) {
+                assert_eq!(Ok(vec![]), mapping.crossbar.defect_changes(&defects));
+                mapped += 1;
+            }
+        }
+        assert!(mapped > 0);
+    }
+}
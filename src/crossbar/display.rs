@@ -0,0 +1,105 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::{Display, Formatter};
+
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+
+impl Display for CrossbarMatrix {
+    /// The crossbar text format, with the devices of each column aligned.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "rows {}", self.rows)?;
+        writeln!(f, "columns {}", self.columns)?;
+        writeln!(f, "input {}", self.input)?;
+        write!(f, "outputs")?;
+        for output in &self.outputs {
+            write!(f, " {output}")?;
+        }
+
+        let cells: Vec<String> = self.cells.iter().map(Literal::to_string).collect();
+        let widths: Vec<usize> = (0..self.columns)
+            .map(|column| {
+                cells
+                    .iter()
+                    .skip(column)
+                    .step_by(self.columns)
+                    .map(String::len)
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in cells.chunks(self.columns) {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{cell:<width$}"))
+                .collect();
+            write!(f, "\n{}", line.join(" ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Literal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Literal::Off => write!(f, "0"),
+            Literal::On => write!(f, "1"),
+            Literal::Positive(variable_id) => write!(f, "x{variable_id}"),
+            Literal::Negative(variable_id) => write!(f, "!x{variable_id}"),
+        }
+    }
+}
+
+impl Display for Wire {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Wire::Row(row) => write!(f, "r{row}"),
+            Wire::Column(column) => write!(f, "c{column}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+
+    #[test]
+    fn aligned() {
+        let mut crossbar =
+            CrossbarMatrix::new(2, 3, Wire::Row(1), vec![Wire::Row(0), Wire::Column(2)]).unwrap();
+        crossbar.set_cell(0, 0, Literal::Negative(10));
+        crossbar.set_cell(1, 1, Literal::Positive(2));
+        crossbar.set_cell(1, 2, Literal::On);
+        assert_eq!(
+            "rows 2
+columns 3
+input r1
+outputs r0 c2
+!x10 0  0
+0    x2 1",
+            crossbar.to_string()
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut crossbar = CrossbarMatrix::new(3, 2, Wire::Column(0), vec![Wire::Row(2)]).unwrap();
+        crossbar.set_cell(2, 1, Literal::Negative(0));
+        let parsed: CrossbarMatrix = crossbar.to_string().parse().unwrap();
+        assert_eq!(crossbar, parsed);
+    }
+}
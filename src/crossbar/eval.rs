@@ -0,0 +1,242 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{convert_bits_to_bools, Evaluate, FlowError};
+
+impl Literal {
+    /// Whether the device conducts under an assignment of the variables.
+    /// # Panics
+    /// The literal's variable has no value in `values`.
+    #[must_use]
+    pub fn conducts(self, values: &[bool]) -> bool {
+        match self {
+            Literal::Off => false,
+            Literal::On => true,
+            Literal::Positive(variable_id) => values[variable_id],
+            Literal::Negative(variable_id) => !values[variable_id],
+        }
+    }
+}
+
+impl CrossbarMatrix {
+    /// Whether current reaches each output wire, in the order of
+    /// [`CrossbarMatrix::outputs`].
+    /// # Errors
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    pub fn eval_outputs(&self, values: &[bool]) -> Result<Vec<bool>, FlowError> {
+        let parents = self.search(values)?;
+        Ok(self
+            .outputs
+            .iter()
+            .map(|&output| parents[self.vertex(output)].is_some())
+            .collect())
+    }
+
+    /// A conducting path from the input to output `output`, as the wires it
+    /// passes through in order, or `None` when no current reaches it. Each
+    /// step between a row and a column goes through the device where they
+    /// cross, and the path is one of the shortest.
+    /// # Errors
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    /// * `EvaluationError` - there is no output `output`
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{CrossbarMatrix, Wire};
+    /// let crossbar: CrossbarMatrix = "rows 2
+    /// columns 2
+    /// input r1
+    /// outputs r0
+    /// x0 0
+    /// x1 0"
+    ///     .parse()
+    ///     .unwrap();
+    /// let path = crossbar.trace(&[true, true], 0).unwrap();
+    /// assert_eq!(
+    ///     Some(vec![Wire::Row(1), Wire::Column(0), Wire::Row(0)]),
+    ///     path
+    /// );
+    /// assert_eq!(None, crossbar.trace(&[true, false], 0).unwrap());
+    /// ```
+    pub fn trace(&self, values: &[bool], output: usize) -> Result<Option<Vec<Wire>>, FlowError> {
+        let &target = self.outputs.get(output).ok_or(EvaluationError(
+            "The crossbar does not have that many outputs",
+        ))?;
+        let parents = self.search(values)?;
+        let mut vertex = self.vertex(target);
+        if parents[vertex].is_none() {
+            return Ok(None);
+        }
+        let mut path = vec![self.wire(vertex)];
+        while let Some(parent) = parents[vertex].filter(|&parent| parent != vertex) {
+            vertex = parent;
+            path.push(self.wire(vertex));
+        }
+        path.reverse();
+        Ok(Some(path))
+    }
+
+    /// Breadth-first search over the wires from the input, treating every
+    /// conducting device as an edge between its row and column. Each reached
+    /// wire records the wire it was reached from; the input records itself.
+    fn search(&self, values: &[bool]) -> Result<Vec<Option<usize>>, FlowError> {
+        if values.len() < self.variables() {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let mut parents = vec![None; self.rows + self.columns];
+        let start = self.vertex(self.input);
+        parents[start] = Some(start);
+        let mut queue = VecDeque::from([start]);
+        while let Some(vertex) = queue.pop_front() {
+            let neighbours: Vec<usize> = match self.wire(vertex) {
+                Wire::Row(row) => (0..self.columns)
+                    .filter(|&column| self.cell(row, column).conducts(values))
+                    .map(|column| self.vertex(Wire::Column(column)))
+                    .collect(),
+                Wire::Column(column) => (0..self.rows)
+                    .filter(|&row| self.cell(row, column).conducts(values))
+                    .map(|row| self.vertex(Wire::Row(row)))
+                    .collect(),
+            };
+            for neighbour in neighbours {
+                if parents[neighbour].is_none() {
+                    parents[neighbour] = Some(vertex);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+        Ok(parents)
+    }
+
+    /// Rows come first, then columns.
+    fn vertex(&self, wire: Wire) -> usize {
+        match wire {
+            Wire::Row(row) => row,
+            Wire::Column(column) => self.rows + column,
+        }
+    }
+
+    fn wire(&self, vertex: usize) -> Wire {
+        if vertex < self.rows {
+            Wire::Row(vertex)
+        } else {
+            Wire::Column(vertex - self.rows)
+        }
+    }
+}
+
+impl Evaluate for CrossbarMatrix {
+    /// Whether current reaches the first output wire.
+    fn eval(&self, values: &[bool]) -> Result<bool, FlowError> { Ok(self.eval_outputs(values)?[0]) }
+
+    fn truth_table(&self) -> Result<Vec<bool>, FlowError> {
+        let variables = self.variables();
+        let combinations = u32::try_from(variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
+        (0..combinations)
+            .map(|var_set| self.eval(&convert_bits_to_bools(var_set, variables)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Wire};
+    use crate::Evaluate;
+
+    /// x0 xor x1: the input row reaches the output row through column 0
+    /// when x0 and not x1, or through column 1 when x1 and not x0.
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn truth_table() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert_eq!(Ok(vec![false, true, true, false]), crossbar.truth_table());
+    }
+
+    #[test]
+    fn outputs() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert_eq!(
+            Ok(vec![false, true]),
+            crossbar.eval_outputs(&[false, false])
+        );
+        assert_eq!(Ok(vec![true, true]), crossbar.eval_outputs(&[false, true]));
+        assert_eq!(Ok(vec![false, false]), crossbar.eval_outputs(&[true, true]));
+    }
+
+    #[test]
+    fn trace() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert_eq!(
+            Ok(Some(vec![Wire::Row(2), Wire::Column(0), Wire::Row(0)])),
+            crossbar.trace(&[true, false], 0)
+        );
+        assert_eq!(
+            Ok(Some(vec![Wire::Row(2), Wire::Column(1)])),
+            crossbar.trace(&[false, false], 1)
+        );
+        assert_eq!(Ok(None), crossbar.trace(&[true, true], 0));
+        assert!(crossbar.trace(&[true, true], 2).is_err());
+    }
+
+    #[test]
+    fn staircase() {
+        let crossbar: CrossbarMatrix = "rows 2
+columns 2
+input r1
+outputs c1
+1 1
+1 0"
+        .parse()
+        .unwrap();
+        assert_eq!(
+            Ok(Some(vec![
+                Wire::Row(1),
+                Wire::Column(0),
+                Wire::Row(0),
+                Wire::Column(1),
+            ])),
+            crossbar.trace(&[], 0)
+        );
+    }
+
+    #[test]
+    fn input_is_output() {
+        let crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Row(0)]).unwrap();
+        assert_eq!(Ok(true), crossbar.eval(&[]));
+        assert_eq!(Ok(Some(vec![Wire::Row(0)])), crossbar.trace(&[], 0));
+    }
+
+    #[test]
+    fn short_assignment() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert!(crossbar.eval(&[true]).is_err());
+    }
+}
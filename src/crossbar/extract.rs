@@ -0,0 +1,221 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+
+use crate::bdd::{Bdd, BddManager, BinaryDecisionDiagram, BinaryOperator};
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+impl CrossbarMatrix {
+    /// The function of each output, in order, computed symbolically in
+    /// `manager`.
+    ///
+    /// Each wire is given the condition under which current reaches it,
+    /// starting from true at the input and false everywhere else. Whenever a
+    /// wire's condition grows, each wire it crosses gains the condition
+    /// and'ed with the device between them, until nothing changes. No
+    /// assignment is ever enumerated, so the cost follows the size of the
+    /// diagrams rather than the number of variables.
+    /// # Errors
+    /// * `EvaluationError` - the manager has fewer variables than the crossbar
+    ///   uses
+    /// # Example
+    /// ```
+    /// use flow::bdd::{BddManager, BinaryOperator};
+    /// use flow::crossbar::CrossbarMatrix;
+    /// let crossbar: CrossbarMatrix = "rows 2
+    /// columns 2
+    /// input r1
+    /// outputs r0
+    /// x0 x1
+    /// 1  1"
+    ///     .parse()
+    ///     .unwrap();
+    /// let manager = BddManager::new(2);
+    /// let or = manager
+    ///     .variable(0)
+    ///     .apply(&manager.variable(1), BinaryOperator::Or)
+    ///     .unwrap();
+    /// assert_eq!(vec![or], crossbar.to_bdds(&manager).unwrap());
+    /// ```
+    pub fn to_bdds(&self, manager: &BddManager) -> Result<Vec<Bdd>, FlowError> {
+        if manager.variables() < self.variables() {
+            return Err(EvaluationError(
+                "The manager has fewer variables than the crossbar uses",
+            ));
+        }
+        let positive: Vec<Bdd> = (0..self.variables())
+            .map(|variable_id| manager.variable(variable_id))
+            .collect();
+        let negative: Vec<Bdd> = positive.iter().map(Bdd::negate).collect();
+        let condition = |literal: Literal| match literal {
+            Literal::Off => None,
+            Literal::On => Some(manager.constant(true)),
+            Literal::Positive(variable_id) => Some(positive[variable_id].clone()),
+            Literal::Negative(variable_id) => Some(negative[variable_id].clone()),
+        };
+
+        let rows = self.rows;
+        let vertex = |wire: Wire| match wire {
+            Wire::Row(row) => row,
+            Wire::Column(column) => rows + column,
+        };
+        let mut reached = vec![manager.constant(false); self.rows + self.columns];
+        reached[vertex(self.input)] = manager.constant(true);
+        let mut queue = VecDeque::from([vertex(self.input)]);
+        let mut queued = vec![false; reached.len()];
+        queued[vertex(self.input)] = true;
+        while let Some(from) = queue.pop_front() {
+            queued[from] = false;
+            let crossings: Vec<(usize, Literal)> = if from < rows {
+                (0..self.columns)
+                    .map(|column| (rows + column, self.cell(from, column)))
+                    .collect()
+            } else {
+                (0..rows)
+                    .map(|row| (row, self.cell(row, from - rows)))
+                    .collect()
+            };
+            for (to, literal) in crossings {
+                let Some(condition) = condition(literal) else {
+                    continue;
+                };
+                let through = reached[from].apply(&condition, BinaryOperator::And)?;
+                let grown = reached[to].apply(&through, BinaryOperator::Or)?;
+                if grown != reached[to] {
+                    reached[to] = grown;
+                    if !queued[to] {
+                        queued[to] = true;
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        Ok(self
+            .outputs
+            .iter()
+            .map(|&output| reached[vertex(output)].clone())
+            .collect())
+    }
+
+    /// The function of each output as a standalone diagram over the
+    /// variables the crossbar uses, computed as in [`CrossbarMatrix::to_bdds`].
+    /// # Errors
+    /// * `EvaluationError` - the diagrams could not be built
+    pub fn to_diagrams(&self) -> Result<Vec<BinaryDecisionDiagram>, FlowError> {
+        let manager = BddManager::new(self.variables());
+        Ok(self
+            .to_bdds(&manager)?
+            .iter()
+            .map(Bdd::to_diagram)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::{BddManager, BinaryDecisionDiagram, BinaryOperator};
+    use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+    use crate::gen::{random_bdd, random_crossbar, BddOptions, CrossbarOptions};
+    use crate::{convert_bits_to_bools, Evaluate};
+
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn outputs() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let diagrams = crossbar.to_diagrams().unwrap();
+        assert_eq!(2, diagrams.len());
+        for var_set in 0..4 {
+            let values = convert_bits_to_bools(var_set, 2);
+            let expected = crossbar.eval_outputs(&values).unwrap();
+            let actual: Vec<bool> = diagrams
+                .iter()
+                .map(|bdd| bdd.eval(&values).unwrap())
+                .collect();
+            assert_eq!(expected, actual);
+        }
+    }
+
+    #[test]
+    fn random() {
+        for seed in 0..30 {
+            let crossbar = random_crossbar(&CrossbarOptions {
+                rows: 6,
+                columns: 5,
+                variables: 4,
+                density: 0.5,
+                seed,
+            })
+            .unwrap();
+            let diagram = &crossbar.to_diagrams().unwrap()[0];
+            assert_eq!(crossbar.truth_table(), diagram.truth_table(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let manager = BddManager::new(6);
+        let diagrams: Vec<BinaryDecisionDiagram> = (0..3)
+            .map(|seed| {
+                random_bdd(&BddOptions {
+                    variables: 6,
+                    nodes: 15,
+                    seed,
+                    ..BddOptions::default()
+                })
+                .unwrap()
+            })
+            .collect();
+        let crossbar = BinaryDecisionDiagram::to_crossbar(&diagrams).unwrap();
+        let extracted = crossbar.to_bdds(&manager).unwrap();
+        for (bdd, extracted) in diagrams.iter().zip(extracted) {
+            assert_eq!(manager.import(bdd).unwrap(), extracted);
+        }
+    }
+
+    #[test]
+    fn many_variables() {
+        // Sixty devices in series, far beyond what a truth table could check.
+        let variables = 60;
+        let mut crossbar = CrossbarMatrix::new(
+            variables / 2 + 1,
+            variables / 2,
+            Wire::Row(0),
+            vec![Wire::Row(variables / 2)],
+        )
+        .unwrap();
+        for index in 0..variables / 2 {
+            crossbar.set_cell(index, index, Literal::Positive(2 * index));
+            crossbar.set_cell(index + 1, index, Literal::Positive(2 * index + 1));
+        }
+        let manager = BddManager::new(variables);
+        let and = (1..variables).fold(manager.variable(0), |and, variable_id| {
+            and.apply(&manager.variable(variable_id), BinaryOperator::And)
+                .unwrap()
+        });
+        assert_eq!(vec![and], crossbar.to_bdds(&manager).unwrap());
+        assert!(crossbar.to_bdds(&BddManager::new(2)).is_err());
+    }
+}
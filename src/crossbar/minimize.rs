@@ -0,0 +1,503 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+use crate::FlowError::EvaluationError;
+use crate::{convert_bits_to_bools, FlowError};
+
+/// Which wires a net is given in the minimized crossbar.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Placement {
+    Row,
+    Column,
+    /// a row and a column joined by an always-on device
+    Both,
+}
+
+impl CrossbarMatrix {
+    /// The number of crossings, the cost minimization reduces.
+    #[must_use]
+    pub fn area(&self) -> usize { self.rows * self.columns }
+
+    /// An equivalent crossbar with as few crossings as the pass can find.
+    ///
+    /// Wires joined by an always-on device are one electrical net and are
+    /// merged. Nets that cannot be on a path from the input to an output are
+    /// dropped, along with duplicate and self-connecting devices. The rest
+    /// are two-coloured into rows and columns so that every device joins a
+    /// row to a column, balancing the two sides to keep the area small; a
+    /// net closing an odd cycle gets both a row and a column joined by an
+    /// always-on device. The result is checked against the original with
+    /// [`CrossbarMatrix::equivalent`], and the original is kept when it is
+    /// already smaller.
+    /// # Errors
+    /// * `EvaluationError` - there are too many variables to check equivalence,
+    ///   or the check failed
+    /// # Example
+    /// ```
+    /// use flow::crossbar::CrossbarMatrix;
+    /// // Row 1 is joined to column 1 by an always-on device, and column 2 is
+    /// // never used.
+    /// let crossbar: CrossbarMatrix = "rows 3
+    /// columns 3
+    /// input r2
+    /// outputs r0
+    /// 0  x1 0
+    /// x0 1  0
+    /// x0 0  0"
+    ///     .parse()
+    ///     .unwrap();
+    /// let minimized = crossbar.minimize().unwrap();
+    /// assert!(minimized.area() < crossbar.area());
+    /// assert!(crossbar.equivalent(&minimized).unwrap());
+    /// ```
+    pub fn minimize(&self) -> Result<Self, FlowError> {
+        let (nets, net_count) = self.nets();
+        let input = nets[self.vertex_of(self.input)];
+        let outputs: Vec<usize> = self
+            .outputs
+            .iter()
+            .map(|&output| nets[self.vertex_of(output)])
+            .collect();
+
+        let mut devices = Vec::new();
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let literal = self.cell(row, column);
+                if matches!(literal, Literal::Positive(_) | Literal::Negative(_)) {
+                    devices.push((nets[row], nets[self.rows + column], literal));
+                }
+            }
+        }
+        let minimized = lay_out(net_count, devices, input, &outputs)?;
+        if !self.equivalent(&minimized)? {
+            return Err(EvaluationError("Minimization changed the function"));
+        }
+        Ok(if minimized.area() < self.area() {
+            minimized
+        } else {
+            self.clone()
+        })
+    }
+
+    /// Whether both crossbars have the same number of outputs and agree on
+    /// every output for every assignment of the variables either uses.
+    /// # Errors
+    /// * `EvaluationError` - there are too many variables to enumerate
+    pub fn equivalent(&self, other: &Self) -> Result<bool, FlowError> {
+        if self.outputs.len() != other.outputs.len() {
+            return Ok(false);
+        }
+        let variables = self.variables().max(other.variables());
+        let combinations = u32::try_from(variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
+        for var_set in 0..combinations {
+            let values = convert_bits_to_bools(var_set, variables);
+            if self.eval_outputs(&values)? != other.eval_outputs(&values)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn vertex_of(&self, wire: Wire) -> usize {
+        match wire {
+            Wire::Row(row) => row,
+            Wire::Column(column) => self.rows + column,
+        }
+    }
+
+    /// The net of every wire, numbered from zero, and the number of nets.
+    /// Wires joined by an always-on device share a net.
+    fn nets(&self) -> (Vec<usize>, usize) {
+        let mut parent: Vec<usize> = (0..self.rows + self.columns).collect();
+        fn find(parent: &mut [usize], mut vertex: usize) -> usize {
+            while parent[vertex] != vertex {
+                parent[vertex] = parent[parent[vertex]];
+                vertex = parent[vertex];
+            }
+            vertex
+        }
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                if self.cell(row, column) == Literal::On {
+                    let (a, b) = (
+                        find(&mut parent, row),
+                        find(&mut parent, self.rows + column),
+                    );
+                    parent[a] = b;
+                }
+            }
+        }
+        let mut numbers = vec![usize::MAX; parent.len()];
+        let mut count = 0;
+        let nets = (0..parent.len())
+            .map(|vertex| {
+                let root = find(&mut parent, vertex);
+                if numbers[root] == usize::MAX {
+                    numbers[root] = count;
+                    count += 1;
+                }
+                numbers[root]
+            })
+            .collect();
+        (nets, count)
+    }
+}
+
+/// A crossbar joining nets `0..net_count` with literal devices, with current
+/// entering at net `input` and leaving at each of `outputs`.
+///
+/// Duplicate and self-connecting devices are dropped, as are nets that cannot
+/// lie on a path from the input to an output. The rest are two-coloured into
+/// rows and columns, and a second device between the same two nets goes
+/// through a fresh net of its own since it would need the same crossing.
+pub(crate) fn lay_out(
+    net_count: usize,
+    devices: impl IntoIterator<Item = (usize, usize, Literal)>,
+    input: usize,
+    outputs: &[usize],
+) -> Result<CrossbarMatrix, FlowError> {
+    let devices: BTreeSet<(usize, usize, Literal)> = devices
+        .into_iter()
+        .filter(|&(a, b, _)| a != b)
+        .map(|(a, b, literal)| (a.min(b), a.max(b), literal))
+        .collect();
+    let mut devices: Vec<(usize, usize, Literal)> = devices.into_iter().collect();
+    let kept = needed(net_count, &devices, input, outputs);
+    devices.retain(|&(a, b, _)| kept[a] && kept[b]);
+
+    let mut net_count = net_count;
+    let mut pairs = HashSet::new();
+    let mut edges: Vec<(usize, usize, Literal)> = Vec::with_capacity(devices.len());
+    let mut links: Vec<(usize, usize)> = Vec::new();
+    for (a, b, literal) in devices {
+        if pairs.insert((a, b)) {
+            edges.push((a, b, literal));
+        } else {
+            edges.push((a, net_count, literal));
+            links.push((net_count, b));
+            net_count += 1;
+        }
+    }
+
+    let mut used = vec![false; net_count];
+    used[input] = true;
+    for &output in outputs {
+        used[output] = true;
+    }
+    for &(a, b, _) in &edges {
+        used[a] = true;
+        used[b] = true;
+    }
+    let placements = place(net_count, &used, &edges, &links);
+    build(&placements, &edges, &links, input, outputs)
+}
+
+/// The nets that can lie on a path from the input to an output: reachable
+/// from the input, and not a dead end unless it is the input or an output.
+fn needed(
+    net_count: usize,
+    devices: &[(usize, usize, Literal)],
+    input: usize,
+    outputs: &[usize],
+) -> Vec<bool> {
+    let mut neighbours = vec![Vec::new(); net_count];
+    for &(a, b, _) in devices {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+    let mut kept = vec![false; net_count];
+    kept[input] = true;
+    let mut queue = VecDeque::from([input]);
+    while let Some(net) = queue.pop_front() {
+        for &next in &neighbours[net] {
+            if !kept[next] {
+                kept[next] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let terminal = |net: usize| net == input || outputs.contains(&net);
+    let mut degree: Vec<usize> = (0..net_count)
+        .map(|net| neighbours[net].iter().filter(|&&next| kept[next]).count())
+        .collect();
+    let mut dead_ends: Vec<usize> = (0..net_count)
+        .filter(|&net| kept[net] && degree[net] <= 1 && !terminal(net))
+        .collect();
+    while let Some(net) = dead_ends.pop() {
+        if !kept[net] {
+            continue;
+        }
+        kept[net] = false;
+        for &next in &neighbours[net] {
+            if kept[next] {
+                degree[next] -= 1;
+                if degree[next] <= 1 && !terminal(next) {
+                    dead_ends.push(next);
+                }
+            }
+        }
+    }
+    kept
+}
+
+/// Two-colour the used nets into rows and columns component by component,
+/// giving a net both when it would join two nets of its own colour, then
+/// flip whole components so the two sides stay balanced.
+fn place(
+    net_count: usize,
+    used: &[bool],
+    edges: &[(usize, usize, Literal)],
+    links: &[(usize, usize)],
+) -> Vec<Option<Placement>> {
+    let mut neighbours = vec![Vec::new(); net_count];
+    for (a, b) in edges
+        .iter()
+        .map(|&(a, b, _)| (a, b))
+        .chain(links.iter().copied())
+    {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    let mut placements: Vec<Option<Placement>> = vec![None; net_count];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for start in 0..net_count {
+        if !used[start] || placements[start].is_some() {
+            continue;
+        }
+        placements[start] = Some(Placement::Row);
+        let mut component = vec![start];
+        let mut queue = VecDeque::from([start]);
+        while let Some(net) = queue.pop_front() {
+            let opposite = match placements[net] {
+                Some(Placement::Row) => Placement::Column,
+                _ => Placement::Row,
+            };
+            for &next in &neighbours[net] {
+                match placements[next] {
+                    None => {
+                        placements[next] = Some(opposite);
+                        component.push(next);
+                        queue.push_back(next);
+                    },
+                    Some(placement)
+                        if placement != Placement::Both && Some(placement) == placements[net] =>
+                    {
+                        placements[next] = Some(Placement::Both);
+                    },
+                    Some(_) => {},
+                }
+            }
+        }
+        components.push(component);
+    }
+
+    // Largest components first, each oriented to even out the two sides.
+    components.sort_by_key(|component| std::cmp::Reverse(component.len()));
+    let (mut rows, mut columns) = (0usize, 0usize);
+    for component in components {
+        let count = |placement| {
+            component
+                .iter()
+                .filter(|&&net| placements[net] == Some(placement))
+                .count()
+        };
+        let (as_rows, as_columns, both) = (
+            count(Placement::Row),
+            count(Placement::Column),
+            count(Placement::Both),
+        );
+        let kept = (rows + as_rows + both).abs_diff(columns + as_columns + both);
+        let flipped = (rows + as_columns + both).abs_diff(columns + as_rows + both);
+        if flipped < kept {
+            for &net in &component {
+                placements[net] = placements[net].map(|placement| match placement {
+                    Placement::Row => Placement::Column,
+                    Placement::Column => Placement::Row,
+                    Placement::Both => Placement::Both,
+                });
+            }
+            rows += as_columns + both;
+            columns += as_rows + both;
+        } else {
+            rows += as_rows + both;
+            columns += as_columns + both;
+        }
+    }
+    placements
+}
+
+/// Lay the placed nets out as a crossbar, with at least one row and column.
+fn build(
+    placements: &[Option<Placement>],
+    edges: &[(usize, usize, Literal)],
+    links: &[(usize, usize)],
+    input: usize,
+    outputs: &[usize],
+) -> Result<CrossbarMatrix, FlowError> {
+    let mut row_of = vec![None; placements.len()];
+    let mut column_of = vec![None; placements.len()];
+    let (mut rows, mut columns) = (0, 0);
+    for (net, placement) in placements.iter().enumerate() {
+        if matches!(placement, Some(Placement::Row | Placement::Both)) {
+            row_of[net] = Some(rows);
+            rows += 1;
+        }
+        if matches!(placement, Some(Placement::Column | Placement::Both)) {
+            column_of[net] = Some(columns);
+            columns += 1;
+        }
+    }
+    let wire = |net: usize| {
+        row_of[net]
+            .map(Wire::Row)
+            .or(column_of[net].map(Wire::Column))
+            .ok_or(EvaluationError("A net was not given a wire"))
+    };
+    let mut crossbar = CrossbarMatrix::new(
+        rows.max(1),
+        columns.max(1),
+        wire(input)?,
+        outputs
+            .iter()
+            .map(|&net| wire(net))
+            .collect::<Result<_, _>>()?,
+    )?;
+
+    for (net, placement) in placements.iter().enumerate() {
+        if let (Some(Placement::Both), Some(row), Some(column)) =
+            (placement, row_of[net], column_of[net])
+        {
+            crossbar.set_cell(row, column, Literal::On);
+        }
+    }
+    let joined = edges
+        .iter()
+        .copied()
+        .chain(links.iter().map(|&(a, b)| (a, b, Literal::On)));
+    for (a, b, literal) in joined {
+        let (row, column) = match (row_of[a], column_of[b], row_of[b], column_of[a]) {
+            (Some(row), Some(column), _, _) | (_, _, Some(row), Some(column)) => (row, column),
+            _ => return Err(EvaluationError("A device joins two wires of the same kind")),
+        };
+        crossbar.set_cell(row, column, literal);
+    }
+    Ok(crossbar)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+    use crate::gen::{random_crossbar, CrossbarOptions};
+
+    #[test]
+    fn drops_unused_wires() {
+        // Only the first column carries anything.
+        let crossbar: CrossbarMatrix = "rows 3
+columns 4
+input r2
+outputs r0
+x0 0 0 x3
+0  0 0 0
+x1 0 0 0"
+            .parse()
+            .unwrap();
+        let minimized = crossbar.minimize().unwrap();
+        assert_eq!(3, minimized.rows() + minimized.columns());
+        assert!(crossbar.equivalent(&minimized).unwrap());
+    }
+
+    #[test]
+    fn merges_always_connected() {
+        let crossbar: CrossbarMatrix = "rows 3
+columns 2
+input r2
+outputs r0
+x0 0
+1  x1
+0  1"
+            .parse()
+            .unwrap();
+        // r2 and c1 are one net, as are r1 and c0.
+        let minimized = crossbar.minimize().unwrap();
+        assert!(minimized.area() < crossbar.area());
+        assert!(crossbar.equivalent(&minimized).unwrap());
+    }
+
+    #[test]
+    fn parallel_devices() {
+        let mut crossbar = CrossbarMatrix::new(2, 2, Wire::Row(0), vec![Wire::Row(1)]).unwrap();
+        // Two paths of two devices each between the same rows.
+        crossbar.set_cell(0, 0, Literal::Positive(0));
+        crossbar.set_cell(1, 0, Literal::Positive(1));
+        crossbar.set_cell(0, 1, Literal::Negative(0));
+        crossbar.set_cell(1, 1, Literal::Negative(1));
+        let minimized = crossbar.minimize().unwrap();
+        assert!(crossbar.equivalent(&minimized).unwrap());
+        assert!(minimized.area() <= crossbar.area());
+    }
+
+    #[test]
+    fn odd_cycle() {
+        // Three nets joined in a triangle need a row and column for one of
+        // them.
+        let crossbar: CrossbarMatrix = "rows 2
+columns 2
+input r0
+outputs c1
+x0 x1
+1  x2"
+            .parse()
+            .unwrap();
+        let minimized = crossbar.minimize().unwrap();
+        assert!(crossbar.equivalent(&minimized).unwrap());
+    }
+
+    #[test]
+    fn random_equivalent() {
+        for seed in 0..40 {
+            let crossbar = random_crossbar(&CrossbarOptions {
+                rows: 6,
+                columns: 6,
+                variables: 5,
+                density: 0.35,
+                seed,
+            })
+            .unwrap();
+            let minimized = crossbar.minimize().unwrap();
+            assert!(minimized.area() <= crossbar.area(), "seed {seed}");
+            assert!(crossbar.equivalent(&minimized).unwrap(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn not_equivalent() {
+        let and: CrossbarMatrix = "rows 2\ncolumns 1\ninput r1\noutputs r0\nx0\nx1"
+            .parse()
+            .unwrap();
+        let or: CrossbarMatrix = "rows 2\ncolumns 2\ninput r1\noutputs r0\nx0 1\n1 x1"
+            .parse()
+            .unwrap();
+        assert!(!and.equivalent(&or).unwrap());
+    }
+}
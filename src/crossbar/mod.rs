@@ -0,0 +1,206 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+mod analog;
+mod defect;
+mod display;
+mod eval;
+mod extract;
+mod minimize;
+mod parse;
+mod paths;
+mod render;
+mod spice;
+mod verilog;
+
+pub use self::analog::{AnalogOptions, NoiseMargin, OutputReading, MAX_MARGIN_VARIABLES};
+pub use self::defect::{Defect, DefectChange, DefectMap, Mapping};
+pub(crate) use self::minimize::lay_out;
+pub use self::parse::LocatedError;
+pub use self::paths::{ConductingPath, PathReport};
+pub use self::render::{Grid, RenderOptions};
+
+/// A grid of row and column nanowires with a memristor at every crossing.
+/// Each device is programmed with a [`Literal`] that decides when it
+/// conducts, and the function is true when current can flow from the input
+/// wire to an output wire.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CrossbarMatrix {
+    rows: usize,
+    columns: usize,
+    /// Devices in row-major order.
+    cells: Vec<Literal>,
+    input: Wire,
+    outputs: Vec<Wire>,
+}
+
+/// When the device at a crossing conducts.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Literal {
+    /// never conducts
+    #[default]
+    Off,
+    /// always conducts
+    On,
+    /// conducts when the variable is true
+    Positive(usize),
+    /// conducts when the variable is false
+    Negative(usize),
+}
+
+/// A nanowire of a crossbar.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Wire {
+    Row(usize),
+    Column(usize),
+}
+
+impl CrossbarMatrix {
+    /// A crossbar with every device off.
+    /// # Errors
+    /// * `ParseError` - the crossbar has no rows or columns, there are no
+    ///   outputs, or a wire is outside the crossbar
+    pub fn new(
+        rows: usize,
+        columns: usize,
+        input: Wire,
+        outputs: Vec<Wire>,
+    ) -> Result<Self, FlowError> {
+        if rows == 0 || columns == 0 {
+            return Err(ParseError("A crossbar needs at least one row and column"));
+        }
+        if outputs.is_empty() {
+            return Err(ParseError("A crossbar needs at least one output wire"));
+        }
+        let crossbar = Self {
+            rows,
+            columns,
+            cells: vec![Literal::Off; rows * columns],
+            input,
+            outputs,
+        };
+        if !crossbar.contains(input) || !crossbar.outputs.iter().all(|&w| crossbar.contains(w)) {
+            return Err(ParseError("Wire is outside the crossbar"));
+        }
+        Ok(crossbar)
+    }
+
+    #[must_use]
+    pub fn rows(&self) -> usize { self.rows }
+
+    #[must_use]
+    pub fn columns(&self) -> usize { self.columns }
+
+    /// The wire current is applied to.
+    #[must_use]
+    pub fn input(&self) -> Wire { self.input }
+
+    /// The wires sensed for each output, in order.
+    #[must_use]
+    pub fn outputs(&self) -> &[Wire] { &self.outputs }
+
+    /// The device at a crossing.
+    /// # Panics
+    /// The crossing is outside the crossbar.
+    #[must_use]
+    pub fn cell(&self, row: usize, column: usize) -> Literal {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Cell is outside the crossbar"
+        );
+        self.cells[row * self.columns + column]
+    }
+
+    /// Program the device at a crossing.
+    /// # Panics
+    /// The crossing is outside the crossbar.
+    pub fn set_cell(&mut self, row: usize, column: usize, literal: Literal) {
+        assert!(
+            row < self.rows && column < self.columns,
+            "Cell is outside the crossbar"
+        );
+        self.cells[row * self.columns + column] = literal;
+    }
+
+    /// One more than the largest variable id used by a device.
+    #[must_use]
+    pub fn variables(&self) -> usize {
+        self.cells
+            .iter()
+            .filter_map(|literal| match literal {
+                Literal::Positive(variable_id) | Literal::Negative(variable_id) => {
+                    Some(variable_id + 1)
+                },
+                Literal::Off | Literal::On => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// The number of devices that are not permanently off.
+    #[must_use]
+    pub fn device_count(&self) -> usize {
+        self.cells
+            .iter()
+            .filter(|&&literal| literal != Literal::Off)
+            .count()
+    }
+
+    /// The share of crossings holding a device that is not permanently off.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn density(&self) -> f64 { self.device_count() as f64 / self.cells.len() as f64 }
+
+    fn contains(&self, wire: Wire) -> bool {
+        match wire {
+            Wire::Row(row) => row < self.rows,
+            Wire::Column(column) => column < self.columns,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+
+    #[test]
+    fn new_is_off() {
+        let crossbar = CrossbarMatrix::new(2, 3, Wire::Row(1), vec![Wire::Row(0)]).unwrap();
+        assert_eq!(0, crossbar.device_count());
+        assert_eq!(Literal::Off, crossbar.cell(1, 2));
+    }
+
+    #[test]
+    fn wire_outside() {
+        assert!(CrossbarMatrix::new(2, 2, Wire::Row(2), vec![Wire::Row(0)]).is_err());
+        assert!(CrossbarMatrix::new(2, 2, Wire::Row(1), vec![Wire::Column(2)]).is_err());
+        assert!(CrossbarMatrix::new(2, 2, Wire::Row(1), vec![]).is_err());
+        assert!(CrossbarMatrix::new(0, 2, Wire::Row(0), vec![Wire::Row(0)]).is_err());
+    }
+
+    #[test]
+    fn variables() {
+        let mut crossbar = CrossbarMatrix::new(2, 2, Wire::Row(1), vec![Wire::Row(0)]).unwrap();
+        crossbar.set_cell(0, 1, Literal::Negative(4));
+        crossbar.set_cell(1, 1, Literal::On);
+        assert_eq!(5, crossbar.variables());
+        assert_eq!(2, crossbar.device_count());
+        assert!((crossbar.density() - 0.5).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,386 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+/// A crossbar that failed to parse, and where. Lines and columns count from
+/// one; a missing line is reported just past the end of the text.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LocatedError {
+    pub line: usize,
+    pub column: usize,
+    pub message: &'static str,
+}
+
+impl Display for LocatedError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Could not parse: line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for LocatedError {}
+
+impl From<LocatedError> for FlowError {
+    fn from(error: LocatedError) -> Self { ParseError(error.message) }
+}
+
+/// A whitespace-separated word and the column it starts at.
+pub(super) type Token<'a> = (usize, &'a str);
+
+/// The non-blank lines of a text with comments removed, split into tokens.
+pub(super) struct Lines<'a> {
+    lines: Box<dyn Iterator<Item = (usize, Vec<Token<'a>>)> + 'a>,
+    last: usize,
+}
+
+impl<'a> Lines<'a> {
+    pub(super) fn new(s: &'a str) -> Self {
+        Self {
+            lines: Box::new(
+                s.lines()
+                    .enumerate()
+                    .map(|(index, line)| (index + 1, tokens(line.split('#').next().unwrap_or(""))))
+                    .filter(|(_, tokens)| !tokens.is_empty()),
+            ),
+            last: s.lines().count(),
+        }
+    }
+
+    pub(super) fn next(&mut self) -> Option<(usize, Vec<Token<'a>>)> { self.lines.next() }
+
+    fn expect(&mut self, missing: &'static str) -> Result<(usize, Vec<Token<'a>>), LocatedError> {
+        self.next().ok_or(LocatedError {
+            line: self.last + 1,
+            column: 1,
+            message: missing,
+        })
+    }
+}
+
+fn tokens(line: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (index, c) in line.char_indices().chain([(line.len(), ' ')]) {
+        match (start, c.is_ascii_whitespace()) {
+            (None, false) => start = Some(index),
+            (Some(begin), true) => {
+                tokens.push((begin + 1, &line[begin..index]));
+                start = None;
+            },
+            _ => {},
+        }
+    }
+    tokens
+}
+
+/// The values after `keyword` on a header line.
+fn header<'t, 'a>(
+    line: usize,
+    tokens: &'t [Token<'a>],
+    keyword: &str,
+    message: &'static str,
+) -> Result<&'t [Token<'a>], LocatedError> {
+    let (column, word) = tokens[0];
+    if word != keyword {
+        return Err(LocatedError {
+            line,
+            column,
+            message,
+        });
+    }
+    Ok(&tokens[1..])
+}
+
+/// The single number on a `rows` or `columns` line.
+fn size(
+    line: usize,
+    tokens: &[Token],
+    keyword: &str,
+    message: &'static str,
+) -> Result<usize, LocatedError> {
+    let values = header(line, tokens, keyword, message)?;
+    let &(column, value) = values.first().ok_or(LocatedError {
+        line,
+        column: tokens[0].0 + keyword.len(),
+        message: "Size is missing",
+    })?;
+    if let Some(&(column, _)) = values.get(1) {
+        return Err(LocatedError {
+            line,
+            column,
+            message: "Unexpected text after the size",
+        });
+    }
+    match value.parse::<usize>() {
+        Ok(size) if size > 0 => Ok(size),
+        Ok(_) => Err(LocatedError {
+            line,
+            column,
+            message: "A crossbar needs at least one row and column",
+        }),
+        Err(_) => Err(LocatedError {
+            line,
+            column,
+            message: "Size must be a non-negative number",
+        }),
+    }
+}
+
+fn wire(
+    line: usize,
+    (column, word): Token,
+    rows: usize,
+    columns: usize,
+) -> Result<Wire, LocatedError> {
+    let error = |message| LocatedError {
+        line,
+        column,
+        message,
+    };
+    let (wire, count) = match (word.get(..1), word.get(1..).map(str::parse::<usize>)) {
+        (Some("r"), Some(Ok(row))) => (Wire::Row(row), rows),
+        (Some("c"), Some(Ok(column))) => (Wire::Column(column), columns),
+        _ => return Err(error("Wire must be r<row> or c<column>")),
+    };
+    match wire {
+        Wire::Row(index) | Wire::Column(index) if index >= count => {
+            Err(error("Wire is outside the crossbar"))
+        },
+        _ => Ok(wire),
+    }
+}
+
+fn literal(line: usize, (column, word): Token) -> Result<Literal, LocatedError> {
+    let variable = |id: &str| {
+        id.parse::<usize>().map_err(|_| LocatedError {
+            line,
+            column,
+            message: "Variable must be x followed by a number",
+        })
+    };
+    match word {
+        "0" => Ok(Literal::Off),
+        "1" => Ok(Literal::On),
+        _ => {
+            if let Some(id) = word.strip_prefix("!x") {
+                Ok(Literal::Negative(variable(id)?))
+            } else if let Some(id) = word.strip_prefix('x') {
+                Ok(Literal::Positive(variable(id)?))
+            } else {
+                Err(LocatedError {
+                    line,
+                    column,
+                    message: "Device must be 0, 1, x<id> or !x<id>",
+                })
+            }
+        },
+    }
+}
+
+impl CrossbarMatrix {
+    /// Read the crossbar text format: `rows`, `columns`, `input` and `outputs`
+    /// lines, then one line of devices per row. Text after `#` is a comment.
+    /// # Errors
+    /// * `LocatedError` - the line and column the text stops making sense at
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{CrossbarMatrix, Literal, Wire};
+    /// let crossbar = CrossbarMatrix::parse(
+    ///     "rows 2
+    /// columns 2
+    /// input r1
+    /// outputs r0
+    /// x0 0
+    /// 1  !x1",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(Wire::Row(1), crossbar.input());
+    /// assert_eq!(Literal::Negative(1), crossbar.cell(1, 1));
+    /// ```
+    pub fn parse(s: &str) -> Result<Self, LocatedError> {
+        let mut lines = Lines::new(s);
+        let (line, tokens) = lines.expect("Rows line not present")?;
+        let rows = size(line, &tokens, "rows", "Expected the rows line")?;
+        let (line, tokens) = lines.expect("Columns line not present")?;
+        let columns = size(line, &tokens, "columns", "Expected the columns line")?;
+        if rows
+            .checked_mul(columns)
+            .is_none_or(|cells| cells > s.len())
+        {
+            return Err(LocatedError {
+                line,
+                column: 1,
+                message: "Crossbar is larger than the text describing it",
+            });
+        }
+
+        let (line, tokens) = lines.expect("Input line not present")?;
+        let input = match header(line, &tokens, "input", "Expected the input line")? {
+            [token] => wire(line, *token, rows, columns)?,
+            [] => {
+                return Err(LocatedError {
+                    line,
+                    column: tokens[0].0 + "input".len(),
+                    message: "Input wire is missing",
+                })
+            },
+            [_, (column, _), ..] => {
+                return Err(LocatedError {
+                    line,
+                    column: *column,
+                    message: "A crossbar has only one input wire",
+                })
+            },
+        };
+        let (line, tokens) = lines.expect("Outputs line not present")?;
+        let outputs = header(line, &tokens, "outputs", "Expected the outputs line")?
+            .iter()
+            .map(|&token| wire(line, token, rows, columns))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut crossbar = Self::new(rows, columns, input, outputs).map_err(|_| LocatedError {
+            line,
+            column: tokens[0].0 + "outputs".len(),
+            message: "A crossbar needs at least one output wire",
+        })?;
+
+        for row in 0..rows {
+            let (line, tokens) = lines.expect("Crossbar has fewer rows than declared")?;
+            if let Some(&(column, _)) = tokens.get(columns) {
+                return Err(LocatedError {
+                    line,
+                    column,
+                    message: "Row has more devices than the crossbar has columns",
+                });
+            }
+            if tokens.len() < columns {
+                let (column, word) = tokens[tokens.len() - 1];
+                return Err(LocatedError {
+                    line,
+                    column: column + word.len(),
+                    message: "Row has fewer devices than the crossbar has columns",
+                });
+            }
+            for (column, &token) in tokens.iter().enumerate() {
+                crossbar.set_cell(row, column, literal(line, token)?);
+            }
+        }
+        if let Some((line, tokens)) = lines.next() {
+            return Err(LocatedError {
+                line,
+                column: tokens[0].0,
+                message: "Crossbar has more rows than declared",
+            });
+        }
+        Ok(crossbar)
+    }
+}
+
+impl FromStr for CrossbarMatrix {
+    type Err = FlowError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> { Ok(Self::parse(s)?) }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Literal, LocatedError, Wire};
+
+    const CROSSBAR: &str = "rows 3
+columns 3
+input r2
+outputs r0 c2
+x0  !x1 0
+1   x12 0  # comment
+0   0   !x0
+";
+
+    fn error(s: &str) -> (usize, usize, &'static str) {
+        let LocatedError {
+            line,
+            column,
+            message,
+        } = CrossbarMatrix::parse(s).unwrap_err();
+        (line, column, message)
+    }
+
+    #[test]
+    fn parse() {
+        let crossbar = CrossbarMatrix::parse(CROSSBAR).unwrap();
+        assert_eq!((3, 3), (crossbar.rows(), crossbar.columns()));
+        assert_eq!(Wire::Row(2), crossbar.input());
+        assert_eq!(&[Wire::Row(0), Wire::Column(2)], crossbar.outputs());
+        assert_eq!(Literal::Negative(1), crossbar.cell(0, 1));
+        assert_eq!(Literal::On, crossbar.cell(1, 0));
+        assert_eq!(Literal::Positive(12), crossbar.cell(1, 1));
+        assert_eq!(Literal::Negative(0), crossbar.cell(2, 2));
+    }
+
+    #[test]
+    fn located() {
+        assert_eq!(
+            (6, 5, "Device must be 0, 1, x<id> or !x<id>"),
+            error(&CROSSBAR.replace("x12", "y12"))
+        );
+        assert_eq!(
+            (4, 12, "Wire is outside the crossbar"),
+            error(&CROSSBAR.replace("c2", "c3"))
+        );
+        assert_eq!((2, 1, "Expected the columns line"), error("rows 1\ncols 1"));
+        assert_eq!(
+            (2, 9, "Size must be a non-negative number"),
+            error("rows 1\ncolumns -1")
+        );
+    }
+
+    #[test]
+    fn row_lengths() {
+        assert_eq!(
+            (5, 11, "Row has more devices than the crossbar has columns"),
+            error(&CROSSBAR.replace("x0  !x1 0", "x0  !x1 0 1"))
+        );
+        assert_eq!(
+            (5, 8, "Row has fewer devices than the crossbar has columns"),
+            error(&CROSSBAR.replace("x0  !x1 0", "x0  !x1"))
+        );
+    }
+
+    #[test]
+    fn row_count() {
+        assert_eq!(
+            (8, 1, "Crossbar has fewer rows than declared"),
+            error(&CROSSBAR.replace("rows 3", "rows 4"))
+        );
+        assert_eq!(
+            (7, 1, "Crossbar has more rows than declared"),
+            error(&CROSSBAR.replace("rows 3", "rows 2").replace("r2", "r1"))
+        );
+    }
+
+    #[test]
+    fn huge_size() {
+        assert!(CrossbarMatrix::parse("rows 100000000\ncolumns 100000000").is_err());
+    }
+}
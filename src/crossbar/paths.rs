@@ -0,0 +1,364 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::VecDeque;
+
+use crate::crossbar::{CrossbarMatrix, Wire};
+use crate::FlowError;
+use crate::FlowError::VariableAssignmentError;
+
+/// A route current takes from the input to an output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConductingPath {
+    /// the wires passed through, from the input to the output
+    pub wires: Vec<Wire>,
+    /// whether the path goes through a device no shortest path uses
+    pub sneak: bool,
+}
+
+impl ConductingPath {
+    /// The number of devices the path goes through.
+    #[must_use]
+    pub fn len(&self) -> usize { self.wires.len() - 1 }
+
+    /// Whether the path goes through no devices, which happens when the
+    /// output is the input wire.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.wires.len() == 1 }
+}
+
+/// Every conducting path to one output under one assignment.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PathReport {
+    /// the simple paths found, shortest first; only some of them when
+    /// `truncated` is set
+    pub paths: Vec<ConductingPath>,
+    /// the number of devices on a shortest path, or `None` when no current
+    /// reaches the output
+    pub shortest: Option<usize>,
+    /// whether the search stopped at the limit before finding every path
+    pub truncated: bool,
+}
+
+impl PathReport {
+    /// The number of sneak paths found, which is only a lower bound when the
+    /// search was truncated.
+    #[must_use]
+    pub fn sneak_count(&self) -> usize { self.paths.iter().filter(|path| path.sneak).count() }
+}
+
+impl CrossbarMatrix {
+    /// Every simple conducting path from the input to each output, in the
+    /// order of [`CrossbarMatrix::outputs`].
+    ///
+    /// A path is a sneak path when it goes through a device that lies on no
+    /// shortest path to its output: an alternative route that still carries
+    /// current and so narrows the analog margins. The shortest length and the
+    /// sneak flag of each path found are exact, but the number of paths can
+    /// grow exponentially, so the search for each output stops after `limit`
+    /// paths and the counts of paths and sneak paths become lower bounds.
+    /// # Errors
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    /// # Example
+    /// ```
+    /// use flow::crossbar::CrossbarMatrix;
+    /// // The direct device r1-c0 and a detour through r0 and c1.
+    /// let crossbar: CrossbarMatrix = "rows 2
+    /// columns 2
+    /// input r1
+    /// outputs c0
+    /// x0 1
+    /// x1 x2"
+    ///     .parse()
+    ///     .unwrap();
+    /// let report = &crossbar.conducting_paths(&[true, true, true], 10).unwrap()[0];
+    /// assert_eq!(Some(1), report.shortest);
+    /// assert_eq!(2, report.paths.len());
+    /// assert_eq!(1, report.sneak_count());
+    /// ```
+    pub fn conducting_paths(
+        &self,
+        values: &[bool],
+        limit: usize,
+    ) -> Result<Vec<PathReport>, FlowError> {
+        if values.len() < self.variables() {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let rows = self.rows;
+        let vertex = |wire: Wire| match wire {
+            Wire::Row(row) => row,
+            Wire::Column(column) => rows + column,
+        };
+        let wire = |vertex: usize| {
+            if vertex < rows {
+                Wire::Row(vertex)
+            } else {
+                Wire::Column(vertex - rows)
+            }
+        };
+        let mut neighbours = vec![Vec::new(); self.rows + self.columns];
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                if self.cell(row, column).conducts(values) {
+                    neighbours[row].push(rows + column);
+                    neighbours[rows + column].push(row);
+                }
+            }
+        }
+        let input = vertex(self.input);
+        let from_input = distances(&neighbours, input);
+
+        Ok(self
+            .outputs
+            .iter()
+            .map(|&output| {
+                let target = vertex(output);
+                let Some(shortest) = from_input[target] else {
+                    return PathReport {
+                        paths: Vec::new(),
+                        shortest: None,
+                        truncated: false,
+                    };
+                };
+                let to_output = distances(&neighbours, target);
+                // A device is on a shortest path when going through it costs
+                // nothing extra in one direction or the other.
+                let on_shortest = |a: usize, b: usize| {
+                    [(a, b), (b, a)].iter().any(|&(from, to)| {
+                        matches!(
+                            (from_input[from], to_output[to]),
+                            (Some(before), Some(after)) if before + 1 + after == shortest
+                        )
+                    })
+                };
+
+                let (found, truncated) = simple_paths(&neighbours, input, target, limit);
+                let mut paths: Vec<ConductingPath> = found
+                    .into_iter()
+                    .map(|vertices| ConductingPath {
+                        sneak: vertices
+                            .windows(2)
+                            .any(|step| !on_shortest(step[0], step[1])),
+                        wires: vertices.into_iter().map(wire).collect(),
+                    })
+                    .collect();
+                paths.sort_by_key(ConductingPath::len);
+                PathReport {
+                    paths,
+                    shortest: Some(shortest),
+                    truncated,
+                }
+            })
+            .collect())
+    }
+}
+
+/// The number of devices between `start` and every wire it reaches.
+fn distances(neighbours: &[Vec<usize>], start: usize) -> Vec<Option<usize>> {
+    let mut distances = vec![None; neighbours.len()];
+    distances[start] = Some(0);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((vertex, distance)) = queue.pop_front() {
+        for &next in &neighbours[vertex] {
+            if distances[next].is_none() {
+                distances[next] = Some(distance + 1);
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Up to `limit` paths from `start` to `target` that visit no wire twice,
+/// found by an iterative depth-first search, and whether there were more.
+///
+/// A wire is only stepped onto when it can still reach `target` without
+/// going back through the path, so every branch ends in at least one path
+/// and the work is polynomial in `limit` rather than in the number of
+/// dead-end detours.
+fn simple_paths(
+    neighbours: &[Vec<usize>],
+    start: usize,
+    target: usize,
+    limit: usize,
+) -> (Vec<Vec<usize>>, bool) {
+    let mut paths = Vec::new();
+    let mut on_path = vec![false; neighbours.len()];
+    on_path[start] = true;
+    // Each entry is a wire on the current path and how many of its
+    // neighbours have been tried.
+    let mut path = vec![(start, 0)];
+    while let Some(&(vertex, tried)) = path.last() {
+        if vertex == target {
+            if paths.len() == limit {
+                return (paths, true);
+            }
+            paths.push(path.iter().map(|&(vertex, _)| vertex).collect());
+        } else if let Some(&next) = neighbours[vertex].get(tried) {
+            let top = path.len() - 1;
+            path[top].1 += 1;
+            if !on_path[next] && reaches(neighbours, next, target, &on_path) {
+                on_path[next] = true;
+                path.push((next, 0));
+            }
+            continue;
+        }
+        on_path[vertex] = false;
+        path.pop();
+    }
+    (paths, false)
+}
+
+/// Whether `from` reaches `target` through wires not on the path.
+fn reaches(neighbours: &[Vec<usize>], from: usize, target: usize, on_path: &[bool]) -> bool {
+    let mut seen = on_path.to_vec();
+    seen[from] = true;
+    let mut stack = vec![from];
+    while let Some(vertex) = stack.pop() {
+        if vertex == target {
+            return true;
+        }
+        for &next in &neighbours[vertex] {
+            if !seen[next] {
+                seen[next] = true;
+                stack.push(next);
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+
+    /// Input r2 reaches output r0 directly through c0, or around through
+    /// c1 and r1.
+    const LADDER: &str = "rows 3
+columns 2
+input r2
+outputs r0 r1
+x0 0
+x1 x2
+x3 1";
+
+    #[test]
+    fn all_paths() {
+        let crossbar: CrossbarMatrix = LADDER.parse().unwrap();
+        let reports = crossbar.conducting_paths(&[true; 4], 100).unwrap();
+        let report = &reports[0];
+        assert_eq!(Some(2), report.shortest);
+        assert!(!report.truncated);
+        let paths: Vec<(Vec<Wire>, bool)> = report
+            .paths
+            .iter()
+            .map(|path| (path.wires.clone(), path.sneak))
+            .collect();
+        assert_eq!(
+            vec![
+                (vec![Wire::Row(2), Wire::Column(0), Wire::Row(0)], false),
+                (
+                    vec![
+                        Wire::Row(2),
+                        Wire::Column(1),
+                        Wire::Row(1),
+                        Wire::Column(0),
+                        Wire::Row(0)
+                    ],
+                    true
+                ),
+            ],
+            paths
+        );
+        assert_eq!(1, report.sneak_count());
+        // Both routes to r1 are as short as each other.
+        assert_eq!(Some(2), reports[1].shortest);
+        assert_eq!(2, reports[1].paths.len());
+        assert_eq!(0, reports[1].sneak_count());
+    }
+
+    #[test]
+    fn no_current() {
+        let crossbar: CrossbarMatrix = LADDER.parse().unwrap();
+        let report = &crossbar.conducting_paths(&[false; 4], 100).unwrap()[0];
+        assert_eq!(None, report.shortest);
+        assert!(report.paths.is_empty());
+    }
+
+    #[test]
+    fn input_is_output() {
+        let crossbar = CrossbarMatrix::new(1, 1, Wire::Row(0), vec![Wire::Row(0)]).unwrap();
+        let report = &crossbar.conducting_paths(&[], 100).unwrap()[0];
+        assert_eq!(Some(0), report.shortest);
+        assert_eq!(1, report.paths.len());
+        assert!(report.paths[0].is_empty());
+    }
+
+    #[test]
+    fn limit() {
+        // Every device on: many routes through the full grid.
+        let mut crossbar = CrossbarMatrix::new(4, 4, Wire::Row(0), vec![Wire::Column(3)]).unwrap();
+        for row in 0..4 {
+            for column in 0..4 {
+                crossbar.set_cell(row, column, Literal::On);
+            }
+        }
+        let report = &crossbar.conducting_paths(&[], 5).unwrap()[0];
+        assert_eq!(5, report.paths.len());
+        assert!(report.truncated);
+        assert_eq!(Some(1), report.shortest);
+        // Only the direct device r0-c3 is on a shortest path, so every longer
+        // path found is a sneak path, but more are left unfound.
+        let longer = report.paths.iter().filter(|path| path.len() > 1).count();
+        assert_eq!(longer, report.sneak_count());
+        let all = &crossbar.conducting_paths(&[], 10_000).unwrap()[0];
+        assert!(!all.truncated);
+        assert!(all.paths.len() > 5);
+        assert_eq!(all.paths.len() - 1, all.sneak_count());
+        assert!(report.sneak_count() < all.sneak_count());
+    }
+
+    #[test]
+    fn dead_end_block() {
+        // One device from the input r0 to the output c9, and a 9x9 block of
+        // always-on devices hanging off the input through c0 that leads
+        // nowhere else. Trying every detour through the block would take
+        // minutes.
+        let size = 9;
+        let mut crossbar =
+            CrossbarMatrix::new(size + 1, size + 1, Wire::Row(0), vec![Wire::Column(size)])
+                .unwrap();
+        crossbar.set_cell(0, size, Literal::On);
+        crossbar.set_cell(0, 0, Literal::On);
+        for row in 1..=size {
+            for column in 0..size {
+                crossbar.set_cell(row, column, Literal::On);
+            }
+        }
+        let report = &crossbar.conducting_paths(&[], 10).unwrap()[0];
+        assert_eq!(1, report.paths.len());
+        assert!(!report.truncated);
+        assert_eq!(0, report.sneak_count());
+    }
+
+    #[test]
+    fn short_assignment() {
+        let crossbar: CrossbarMatrix = LADDER.parse().unwrap();
+        assert!(crossbar.conducting_paths(&[true], 10).is_err());
+    }
+}
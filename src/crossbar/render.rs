@@ -0,0 +1,381 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter, Write};
+
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+
+/// Distance between neighbouring wires in an SVG drawing.
+const PITCH: usize = 48;
+/// Space around the wires of an SVG drawing for the labels.
+const MARGIN: usize = 96;
+
+/// How [`CrossbarMatrix::grid`] draws a crossbar.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderOptions<'a> {
+    /// box-drawing characters instead of ASCII
+    pub unicode: bool,
+    /// a conducting path to highlight, as given by [`CrossbarMatrix::trace`]
+    pub path: Option<&'a [Wire]>,
+}
+
+/// A crossbar drawn as a labelled grid for the terminal.
+///
+/// Each cell shows its device, blank when it is off. Wires are labelled with
+/// `in` for the input and `out` for an output, numbered when there are
+/// several. Wires on the highlighted path are marked with `*` and the
+/// devices it passes through are bracketed.
+#[derive(Clone, Copy, Debug)]
+pub struct Grid<'a> {
+    crossbar: &'a CrossbarMatrix,
+    options: RenderOptions<'a>,
+}
+
+/// The wires and crossings a path passes through.
+struct Highlight {
+    wires: HashSet<Wire>,
+    cells: HashSet<(usize, usize)>,
+}
+
+impl Highlight {
+    fn new(path: Option<&[Wire]>) -> Self {
+        let path = path.unwrap_or_default();
+        let cells =
+            path.windows(2)
+                .filter_map(|step| match *step {
+                    [Wire::Row(row), Wire::Column(column)]
+                    | [Wire::Column(column), Wire::Row(row)] => Some((row, column)),
+                    _ => None,
+                })
+                .collect();
+        Self {
+            wires: path.iter().copied().collect(),
+            cells,
+        }
+    }
+}
+
+impl CrossbarMatrix {
+    /// A drawing of the crossbar that displays as a labelled grid.
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{CrossbarMatrix, RenderOptions};
+    /// let crossbar: CrossbarMatrix = "rows 2
+    /// columns 1
+    /// input r1
+    /// outputs r0
+    /// x0
+    /// !x1"
+    /// .parse()
+    /// .unwrap();
+    /// let path = crossbar.trace(&[true, false], 0).unwrap().unwrap();
+    /// let options = RenderOptions {
+    ///     path: Some(&path),
+    ///     ..RenderOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     "          *c0
+    ///        +-------+
+    /// *r0 out| [x0]  |
+    ///        +-------+
+    ///  *r1 in| [!x1] |
+    ///        +-------+",
+    ///     crossbar.grid(options).to_string()
+    /// );
+    /// ```
+    #[must_use]
+    pub fn grid<'a>(&'a self, options: RenderOptions<'a>) -> Grid<'a> {
+        Grid {
+            crossbar: self,
+            options,
+        }
+    }
+
+    /// A drawing of the crossbar as an SVG image. Rows run across and columns
+    /// down, with a device drawn where they cross unless it is off. The input
+    /// wire is blue, the outputs green, and the wires and devices of `path`
+    /// red.
+    #[must_use]
+    pub fn to_svg(&self, path: Option<&[Wire]>) -> String {
+        let highlight = Highlight::new(path);
+        let x = |column: usize| MARGIN + column * PITCH;
+        let y = |row: usize| MARGIN + row * PITCH;
+        let (width, height) = (x(self.columns - 1) + MARGIN, y(self.rows - 1) + MARGIN);
+        let stroke = |wire: Wire| {
+            if highlight.wires.contains(&wire) {
+                ("#d62728", 4)
+            } else if wire == self.input {
+                ("#1f77b4", 3)
+            } else if self.outputs.contains(&wire) {
+                ("#2ca02c", 3)
+            } else {
+                ("#999999", 2)
+            }
+        };
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="monospace" font-size="12">"#
+        );
+        let _ = writeln!(
+            svg,
+            r#"<rect width="{width}" height="{height}" fill="white"/>"#
+        );
+        for row in 0..self.rows {
+            let wire = Wire::Row(row);
+            let (colour, thickness) = stroke(wire);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{start}" y1="{at}" x2="{end}" y2="{at}" stroke="{colour}" stroke-width="{thickness}"/>"#,
+                start = x(0) - PITCH / 2,
+                end = x(self.columns - 1) + PITCH / 2,
+                at = y(row)
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="end" dominant-baseline="middle">{}</text>"#,
+                x(0) - PITCH / 2 - 4,
+                y(row),
+                self.label(wire)
+            );
+        }
+        for column in 0..self.columns {
+            let wire = Wire::Column(column);
+            let (colour, thickness) = stroke(wire);
+            let _ = writeln!(
+                svg,
+                r#"<line x1="{at}" y1="{start}" x2="{at}" y2="{end}" stroke="{colour}" stroke-width="{thickness}"/>"#,
+                at = x(column),
+                start = y(0) - PITCH / 2,
+                end = y(self.rows - 1) + PITCH / 2
+            );
+            let _ = writeln!(
+                svg,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                x(column),
+                y(0) - PITCH / 2 - 6,
+                self.label(wire)
+            );
+        }
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let colour = if highlight.cells.contains(&(row, column)) {
+                    "#d62728"
+                } else {
+                    "black"
+                };
+                let (cx, cy) = (x(column), y(row));
+                match self.cell(row, column) {
+                    Literal::Off => {},
+                    Literal::On => {
+                        let _ = writeln!(
+                            svg,
+                            r#"<circle cx="{cx}" cy="{cy}" r="6" fill="{colour}"/>"#
+                        );
+                    },
+                    literal => {
+                        let _ = writeln!(
+                            svg,
+                            r#"<circle cx="{cx}" cy="{cy}" r="15" fill="white" stroke="{colour}" stroke-width="2"/>"#
+                        );
+                        let _ = writeln!(
+                            svg,
+                            r#"<text x="{cx}" y="{cy}" text-anchor="middle" dominant-baseline="central" fill="{colour}">{literal}</text>"#
+                        );
+                    },
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// The name of a wire followed by what it is connected to, if anything.
+    fn label(&self, wire: Wire) -> String {
+        let mut label = wire.to_string();
+        if wire == self.input {
+            label.push_str(" in");
+        }
+        for (index, &output) in self.outputs.iter().enumerate() {
+            if output == wire {
+                if self.outputs.len() == 1 {
+                    label.push_str(" out");
+                } else {
+                    let _ = write!(label, " out{index}");
+                }
+            }
+        }
+        label
+    }
+}
+
+impl Display for Grid<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let crossbar = self.crossbar;
+        let highlight = Highlight::new(self.options.path);
+        let (horizontal, vertical, corners) = if self.options.unicode {
+            (
+                '─',
+                '│',
+                [['┌', '┬', '┐'], ['├', '┼', '┤'], ['└', '┴', '┘']],
+            )
+        } else {
+            ('-', '|', [['+'; 3]; 3])
+        };
+        let label = |wire: Wire| {
+            let mark = if highlight.wires.contains(&wire) {
+                "*"
+            } else {
+                ""
+            };
+            format!("{mark}{}", crossbar.label(wire))
+        };
+        let cell = |row: usize, column: usize| {
+            let device = match crossbar.cell(row, column) {
+                Literal::Off => String::new(),
+                literal => literal.to_string(),
+            };
+            if highlight.cells.contains(&(row, column)) {
+                format!("[{device}]")
+            } else {
+                device
+            }
+        };
+
+        let row_labels: Vec<String> = (0..crossbar.rows)
+            .map(|row| label(Wire::Row(row)))
+            .collect();
+        let indent = row_labels
+            .iter()
+            .map(|label| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        let column_labels: Vec<String> = (0..crossbar.columns)
+            .map(|column| label(Wire::Column(column)))
+            .collect();
+        let widths: Vec<usize> = (0..crossbar.columns)
+            .map(|column| {
+                (0..crossbar.rows)
+                    .map(|row| cell(row, column).chars().count())
+                    .chain([column_labels[column].chars().count()])
+                    .max()
+                    .unwrap_or(0)
+                    + 2
+            })
+            .collect();
+        let border = |[left, middle, right]: [char; 3]| {
+            let segments: Vec<String> = widths
+                .iter()
+                .map(|&width| horizontal.to_string().repeat(width))
+                .collect();
+            format!(
+                "{:indent$}{left}{}{right}",
+                "",
+                segments.join(&middle.to_string())
+            )
+        };
+
+        let header: Vec<String> = column_labels
+            .iter()
+            .zip(&widths)
+            .map(|(label, &width)| format!("{label:^width$}"))
+            .collect();
+        write!(f, "{:indent$} {}", "", header.join(" ").trim_end())?;
+        write!(f, "\n{}", border(corners[0]))?;
+        for (row, row_label) in row_labels.iter().enumerate() {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(column, &width)| format!("{:^width$}", cell(row, column)))
+                .collect();
+            write!(
+                f,
+                "\n{row_label:>indent$}{vertical}{}{vertical}",
+                cells.join(&vertical.to_string())
+            )?;
+            let corners = if row + 1 == crossbar.rows {
+                corners[2]
+            } else {
+                corners[1]
+            };
+            write!(f, "\n{}", border(corners))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, RenderOptions, Wire};
+
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn ascii() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert_eq!(
+            "         c0    c1 out1
+       +-----+---------+
+r0 out0| !x1 |   x1    |
+       +-----+---------+
+     r1|     |         |
+       +-----+---------+
+  r2 in| x0  |   !x0   |
+       +-----+---------+",
+            crossbar.grid(RenderOptions::default()).to_string()
+        );
+    }
+
+    #[test]
+    fn unicode_path() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let path = crossbar.trace(&[true, false], 0).unwrap().unwrap();
+        let grid = crossbar
+            .grid(RenderOptions {
+                unicode: true,
+                path: Some(&path),
+            })
+            .to_string();
+        let lines: Vec<&str> = grid.lines().collect();
+        assert_eq!("           *c0    c1 out1", lines[0]);
+        assert_eq!("        ┌───────┬─────────┐", lines[1]);
+        assert_eq!("*r0 out0│ [!x1] │   x1    │", lines[2]);
+        assert_eq!("  *r2 in│ [x0]  │   !x0   │", lines[6]);
+        assert_eq!("        └───────┴─────────┘", lines[7]);
+    }
+
+    #[test]
+    fn svg() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let path = [Wire::Row(2), Wire::Column(1)];
+        let svg = crossbar.to_svg(Some(&path));
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(5, svg.matches("<line").count());
+        assert_eq!(4, svg.matches("<circle").count());
+        assert_eq!(3, svg.matches("stroke=\"#d62728\"").count());
+        assert!(svg.contains(">c1 out1</text>"));
+        assert!(svg.contains("fill=\"#d62728\">!x0</text>"));
+    }
+}
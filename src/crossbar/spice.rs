@@ -0,0 +1,191 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Write;
+
+use crate::crossbar::{AnalogOptions, CrossbarMatrix, Literal};
+use crate::FlowError;
+use crate::FlowError::VariableAssignmentError;
+
+impl CrossbarMatrix {
+    /// A SPICE netlist of the crossbar for ngspice, matching
+    /// [`CrossbarMatrix::simulate`]. Experimental: the netlists follow
+    /// ngspice's syntax but are not run through ngspice by the tests.
+    ///
+    /// Row and column wires are nodes `r<n>` and `c<n>`. Every crossing is an
+    /// instance of a `memristor` subcircuit whose resistance is `ron` or `roff`
+    /// depending on its `state`. A device's state is an expression of the
+    /// `x<id>` parameters, so editing the `.param` line switches the
+    /// assignment without touching the devices. `values` sets those
+    /// parameters, or sets them all to 0 when not given. A DC source drives
+    /// the input wire, each output has a sense resistor to ground, and the
+    /// control block prints the output voltages.
+    /// # Errors
+    /// * `EvaluationError` - a resistance or the voltage is not positive and
+    ///   finite
+    /// * `VariableAssignmentError` - fewer values than variables were given
+    /// # Example
+    /// ```
+    /// use flow::crossbar::{AnalogOptions, CrossbarMatrix};
+    /// let crossbar: CrossbarMatrix = "rows 1
+    /// columns 1
+    /// input r0
+    /// outputs c0
+    /// !x0"
+    /// .parse()
+    /// .unwrap();
+    /// let netlist = crossbar
+    ///     .to_spice(Some(&[true]), &AnalogOptions::default())
+    ///     .unwrap();
+    /// assert!(netlist.contains(".param x0=1"));
+    /// assert!(netlist.contains("Xr0c0 r0 c0 memristor params: state={1-x0}"));
+    /// ```
+    pub fn to_spice(
+        &self,
+        values: Option<&[bool]>,
+        options: &AnalogOptions,
+    ) -> Result<String, FlowError> {
+        options.validate()?;
+        let AnalogOptions {
+            r_on,
+            r_off,
+            r_sense,
+            voltage,
+        } = *options;
+        let variables = self.variables();
+        if values.is_some_and(|values| values.len() < variables) {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+
+        let mut netlist = String::new();
+        let _ = writeln!(
+            netlist,
+            "* Crossbar matrix, {} rows by {} columns",
+            self.rows, self.columns
+        );
+        let _ = writeln!(
+            netlist,
+            ".param ron={r_on} roff={r_off} rsense={r_sense} vin={voltage}"
+        );
+        if variables > 0 {
+            let assignment: Vec<String> = (0..variables)
+                .map(|id| {
+                    let value = values.is_some_and(|values| values[id]);
+                    format!("x{id}={}", u8::from(value))
+                })
+                .collect();
+            let _ = writeln!(netlist, ".param {}", assignment.join(" "));
+        }
+        netlist.push_str(
+            "
+.subckt memristor a b params: state=0
+R1 a b {state > 0.5 ? ron : roff}
+.ends memristor
+
+",
+        );
+        let _ = writeln!(netlist, "Vin {} 0 DC {{vin}}", self.input);
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let state = match self.cell(row, column) {
+                    Literal::Off => "0".to_string(),
+                    Literal::On => "1".to_string(),
+                    Literal::Positive(id) => format!("{{x{id}}}"),
+                    Literal::Negative(id) => format!("{{1-x{id}}}"),
+                };
+                let _ = writeln!(
+                    netlist,
+                    "Xr{row}c{column} r{row} c{column} memristor params: state={state}"
+                );
+            }
+        }
+        for (index, output) in self.outputs.iter().enumerate() {
+            let _ = writeln!(netlist, "Rsense{index} {output} 0 {{rsense}}");
+        }
+
+        let probes: Vec<String> = self
+            .outputs
+            .iter()
+            .map(|output| format!("v({output})"))
+            .collect();
+        let _ = write!(
+            netlist,
+            "
+.control
+op
+print {}
+.endc
+.end
+",
+            probes.join(" ")
+        );
+        Ok(netlist)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{AnalogOptions, CrossbarMatrix};
+
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn netlist() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let netlist = crossbar
+            .to_spice(Some(&[true, false]), &AnalogOptions::default())
+            .unwrap();
+        let lines: Vec<&str> = netlist.lines().collect();
+        assert!(lines.contains(&".param ron=1000 roff=1000000 rsense=1000 vin=1"));
+        assert!(lines.contains(&".param x0=1 x1=0"));
+        assert!(lines.contains(&"Vin r2 0 DC {vin}"));
+        assert!(lines.contains(&"Xr0c0 r0 c0 memristor params: state={1-x1}"));
+        assert!(lines.contains(&"Xr1c0 r1 c0 memristor params: state=0"));
+        assert!(lines.contains(&"Xr2c1 r2 c1 memristor params: state={1-x0}"));
+        assert!(lines.contains(&"Rsense1 c1 0 {rsense}"));
+        assert!(lines.contains(&"print v(r0) v(c1)"));
+        assert_eq!(6, lines.iter().filter(|line| line.starts_with('X')).count());
+        assert_eq!(Some(&".end"), lines.last());
+    }
+
+    #[test]
+    fn parameterised() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        let netlist = crossbar.to_spice(None, &AnalogOptions::default()).unwrap();
+        assert!(netlist.contains(".param x0=0 x1=0\n"));
+    }
+
+    #[test]
+    fn invalid() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert!(crossbar
+            .to_spice(Some(&[true]), &AnalogOptions::default())
+            .is_err());
+        let options = AnalogOptions {
+            r_off: f64::INFINITY,
+            ..AnalogOptions::default()
+        };
+        assert!(crossbar.to_spice(None, &options).is_err());
+    }
+}
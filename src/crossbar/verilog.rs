@@ -0,0 +1,212 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::fmt::Write;
+
+use crate::bdd::is_identifier;
+use crate::crossbar::{CrossbarMatrix, Literal, Wire};
+use crate::FlowError;
+use crate::FlowError::ParseError;
+
+impl CrossbarMatrix {
+    /// Write a behavioural Verilog module computing whether current reaches
+    /// each output. Variable `k` becomes input port `x<k>`; a single output
+    /// becomes output port `f`, and several become `f0`, `f1` and so on in
+    /// the order of [`CrossbarMatrix::outputs`].
+    ///
+    /// The module keeps one bit per wire for whether it is reached. Starting
+    /// from the input wire alone, every column then every row takes in the
+    /// wires it crosses through a conducting device, repeated once per wire
+    /// so that the longest simple path is always covered.
+    /// # Errors
+    /// * `ParseError` - `module` is not a valid Verilog identifier
+    /// # Example
+    /// ```
+    /// use flow::crossbar::CrossbarMatrix;
+    /// let crossbar: CrossbarMatrix = "rows 2
+    /// columns 1
+    /// input r1
+    /// outputs r0
+    /// 1
+    /// x0"
+    /// .parse()
+    /// .unwrap();
+    /// let verilog = crossbar.to_verilog("identity").unwrap();
+    /// assert!(verilog.contains("column[0] = column[0] | row[0] | (row[1] & x0);"));
+    /// assert!(verilog.contains("assign f = row[0];"));
+    /// ```
+    pub fn to_verilog(&self, module: &str) -> Result<String, FlowError> {
+        if !is_identifier(module) {
+            return Err(ParseError("Module name is not a valid Verilog identifier"));
+        }
+        let outputs: Vec<String> = if self.outputs.len() == 1 {
+            vec!["f".to_string()]
+        } else {
+            (0..self.outputs.len())
+                .map(|index| format!("f{index}"))
+                .collect()
+        };
+
+        let mut out = String::new();
+        let ports: Vec<String> = (0..self.variables())
+            .map(|variable| format!("    input wire x{variable},\n"))
+            .chain(
+                outputs
+                    .iter()
+                    .map(|output| format!("    output wire {output},\n")),
+            )
+            .collect();
+        let ports = ports.concat();
+        let _ = write!(
+            out,
+            "module {module} (\n{}\n);\n",
+            ports.trim_end_matches(",\n")
+        );
+        let _ = writeln!(out, "    reg [{}:0] row;", self.rows - 1);
+        let _ = writeln!(out, "    reg [{}:0] column;", self.columns - 1);
+        out.push_str("    integer step;\n    always @* begin\n");
+        let start = |count: usize, index: Option<usize>| -> String {
+            (0..count)
+                .rev()
+                .map(|bit| if Some(bit) == index { '1' } else { '0' })
+                .collect()
+        };
+        let (row, column) = match self.input {
+            Wire::Row(row) => (Some(row), None),
+            Wire::Column(column) => (None, Some(column)),
+        };
+        let _ = writeln!(
+            out,
+            "        row = {}'b{};",
+            self.rows,
+            start(self.rows, row)
+        );
+        let _ = writeln!(
+            out,
+            "        column = {}'b{};",
+            self.columns,
+            start(self.columns, column)
+        );
+        let _ = writeln!(
+            out,
+            "        for (step = 0; step < {}; step = step + 1) begin",
+            self.rows + self.columns
+        );
+        for column in 0..self.columns {
+            let terms: Vec<String> = (0..self.rows)
+                .filter_map(|row| term(&format!("row[{row}]"), self.cell(row, column)))
+                .collect();
+            if !terms.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "            column[{column}] = column[{column}] | {};",
+                    terms.join(" | ")
+                );
+            }
+        }
+        for row in 0..self.rows {
+            let terms: Vec<String> = (0..self.columns)
+                .filter_map(|column| term(&format!("column[{column}]"), self.cell(row, column)))
+                .collect();
+            if !terms.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "            row[{row}] = row[{row}] | {};",
+                    terms.join(" | ")
+                );
+            }
+        }
+        out.push_str("        end\n    end\n");
+        for (name, wire) in outputs.iter().zip(&self.outputs) {
+            let _ = match wire {
+                Wire::Row(row) => writeln!(out, "    assign {name} = row[{row}];"),
+                Wire::Column(column) => writeln!(out, "    assign {name} = column[{column}];"),
+            };
+        }
+        out.push_str("endmodule\n");
+        Ok(out)
+    }
+}
+
+/// Whether current passes from a wire through a device, or `None` when the
+/// device is off.
+fn term(wire: &str, literal: Literal) -> Option<String> {
+    match literal {
+        Literal::Off => None,
+        Literal::On => Some(wire.to_string()),
+        Literal::Positive(variable_id) => Some(format!("({wire} & x{variable_id})")),
+        Literal::Negative(variable_id) => Some(format!("({wire} & ~x{variable_id})")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{CrossbarMatrix, Wire};
+
+    const XOR: &str = "rows 3
+columns 2
+input r2
+outputs r0 c1
+!x1 x1
+0   0
+x0  !x0";
+
+    #[test]
+    fn xor() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert_eq!(
+            "module xor2 (
+    input wire x0,
+    input wire x1,
+    output wire f0,
+    output wire f1
+);
+    reg [2:0] row;
+    reg [1:0] column;
+    integer step;
+    always @* begin
+        row = 3'b100;
+        column = 2'b00;
+        for (step = 0; step < 5; step = step + 1) begin
+            column[0] = column[0] | (row[0] & ~x1) | (row[2] & x0);
+            column[1] = column[1] | (row[0] & x1) | (row[2] & ~x0);
+            row[0] = row[0] | (column[0] & ~x1) | (column[1] & x1);
+            row[2] = row[2] | (column[0] & x0) | (column[1] & ~x0);
+        end
+    end
+    assign f0 = row[0];
+    assign f1 = column[1];
+endmodule
+",
+            crossbar.to_verilog("xor2").unwrap()
+        );
+    }
+
+    #[test]
+    fn column_input() {
+        let crossbar = CrossbarMatrix::new(1, 2, Wire::Column(1), vec![Wire::Column(1)]).unwrap();
+        let verilog = crossbar.to_verilog("wire_only").unwrap();
+        assert!(verilog.contains("    output wire f\n);\n"));
+        assert!(verilog.contains("        column = 2'b10;\n"));
+        assert!(verilog.contains("    assign f = column[1];\n"));
+    }
+
+    #[test]
+    fn invalid_module_name() {
+        let crossbar: CrossbarMatrix = XOR.parse().unwrap();
+        assert!(crossbar.to_verilog("1bad").is_err());
+    }
+}
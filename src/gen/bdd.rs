@@ -0,0 +1,310 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashSet;
+
+use crate::bdd::{BinaryDecisionDiagram, SourceNode};
+use crate::gen::Rng;
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+/// Random tries at a fresh pair of children before scanning for one.
+const PAIR_TRIES: usize = 64;
+
+/// How the variables of a random diagram are ordered along its paths.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Order {
+    /// every path tests variables in ascending id order
+    #[default]
+    Ascending,
+    /// every path follows the same random permutation of the variables
+    Shuffled,
+    /// each node tests a random variable, so paths disagree on the order and
+    /// may test a variable more than once
+    Unordered,
+}
+
+/// Shape of a diagram made by [`random_bdd`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BddOptions {
+    /// number of declared variables
+    pub variables: usize,
+    /// number of decision nodes, all reachable from the root
+    pub nodes: usize,
+    pub order: Order,
+    /// whether to avoid redundant tests and duplicate nodes
+    pub reduced: bool,
+    pub seed: u64,
+}
+
+impl Default for BddOptions {
+    fn default() -> Self {
+        Self {
+            variables: 4,
+            nodes: 8,
+            order: Order::Ascending,
+            reduced: true,
+            seed: 0,
+        }
+    }
+}
+
+/// A random diagram with exactly `options.nodes` decision nodes, all
+/// reachable from the root, and both terminals. Node ids are a random
+/// permutation so the written file is not in level order.
+///
+/// Nodes are laid out in levels, each at most twice as wide as the one above
+/// it, so that every node can be given a parent. With `reduced` set, every
+/// node has two different children and no two nodes test the same variable
+/// with the same children; with an ordered `order` that makes the result the
+/// canonical reduced ordered diagram of its function.
+/// # Errors
+/// * `EvaluationError` - there are decision nodes but no variables, or more
+///   nodes than the variables can hold
+/// # Example
+/// ```
+/// use flow::gen::{random_bdd, BddOptions};
+/// let bdd = random_bdd(&BddOptions {
+///     variables: 6,
+///     nodes: 12,
+///     seed: 42,
+///     ..BddOptions::default()
+/// })
+/// .unwrap();
+/// assert_eq!(12 + 2, bdd.node_count());
+/// ```
+pub fn random_bdd(options: &BddOptions) -> Result<BinaryDecisionDiagram, FlowError> {
+    let BddOptions {
+        variables,
+        nodes,
+        order,
+        reduced,
+        seed,
+    } = *options;
+    if nodes == 0 {
+        return Err(EvaluationError(
+            "A diagram needs at least one decision node",
+        ));
+    }
+    if variables == 0 {
+        return Err(EvaluationError("Decision nodes need at least one variable"));
+    }
+    let mut rng = Rng::new(seed);
+
+    // The variable tested at each used level, top first.
+    let mut levels: Vec<usize> = (0..variables).collect();
+    if order == Order::Shuffled {
+        rng.shuffle(&mut levels);
+    }
+    let mut used: Vec<bool> = (0..variables).map(|level| level < nodes).collect();
+    rng.shuffle(&mut used);
+    let levels: Vec<usize> = levels
+        .into_iter()
+        .zip(used)
+        .filter_map(|(variable_id, used)| used.then_some(variable_id))
+        .collect();
+    let sizes = level_sizes(nodes, levels.len(), reduced, &mut rng)?;
+
+    // Ids 0 and 1 are the terminals, decision nodes follow bottom up.
+    let mut triples: Vec<(usize, usize, usize)> = Vec::with_capacity(nodes);
+    let mut candidates: Vec<usize> = vec![0, 1];
+    let mut orphans: Vec<usize> = Vec::new();
+    let mut seen: HashSet<(usize, usize, usize)> = HashSet::new();
+    for (level, &size) in sizes.iter().enumerate().rev() {
+        let mut created = Vec::with_capacity(size);
+        for _ in 0..size {
+            let variable_id = match order {
+                Order::Unordered => rng.below(variables),
+                Order::Ascending | Order::Shuffled => levels[level],
+            };
+            let (low, high) = children(
+                variable_id,
+                &candidates,
+                &mut orphans,
+                reduced.then_some(&seen),
+                &mut rng,
+            )?;
+            seen.insert((variable_id, low, high));
+            triples.push((variable_id, low, high));
+            created.push(triples.len() + 1);
+        }
+        candidates.extend(&created);
+        orphans.extend(created);
+    }
+    let root = orphans
+        .pop()
+        .ok_or(EvaluationError("The diagram has no root"))?;
+
+    let mut ids: Vec<usize> = (0..nodes + 2).collect();
+    rng.shuffle(&mut ids);
+    let source =
+        [SourceNode::Terminal(false), SourceNode::Terminal(true)]
+            .into_iter()
+            .chain(triples.into_iter().map(|(variable_id, low, high)| {
+                SourceNode::Decision(variable_id, ids[low], ids[high])
+            }))
+            .enumerate()
+            .map(|(id, node)| (ids[id], node))
+            .collect();
+    BinaryDecisionDiagram::arrange(variables, source, ids[root])
+        .map_err(|_| EvaluationError("Generated an invalid diagram"))
+}
+
+/// The number of nodes on each used level, top first. The top level holds
+/// only the root, each level is at most twice as wide as the one above, and
+/// a reduced level holds no more nodes than it has distinct pairs of
+/// children.
+fn level_sizes(
+    nodes: usize,
+    levels: usize,
+    reduced: bool,
+    rng: &mut Rng,
+) -> Result<Vec<usize>, FlowError> {
+    let mut sizes = vec![1; levels];
+    for _ in levels..nodes {
+        let open: Vec<usize> = (1..levels)
+            .filter(|&level| {
+                let below = 2 + sizes[level + 1..].iter().sum::<usize>();
+                sizes[level] < 2 * sizes[level - 1]
+                    && (!reduced || sizes[level] < below.saturating_mul(below - 1))
+            })
+            .collect();
+        if open.is_empty() {
+            return Err(EvaluationError(
+                "Too many nodes for the number of variables",
+            ));
+        }
+        sizes[open[rng.below(open.len())]] += 1;
+    }
+    Ok(sizes)
+}
+
+/// Pick the `(low, high)` children of a new node. Nodes still without a
+/// parent are taken first so every node ends up reachable; when `seen` is
+/// given the children differ and the node is not a duplicate.
+fn children(
+    variable_id: usize,
+    candidates: &[usize],
+    orphans: &mut Vec<usize>,
+    seen: Option<&HashSet<(usize, usize, usize)>>,
+    rng: &mut Rng,
+) -> Result<(usize, usize), FlowError> {
+    let mut take_orphan =
+        || (!orphans.is_empty()).then(|| orphans.swap_remove(rng.below(orphans.len())));
+    let (first, second) = (take_orphan(), take_orphan());
+    let mut pair = match (first, second, seen) {
+        (Some(first), Some(second), _) => (first, second),
+        (Some(orphan), None, Some(_)) => {
+            let other = loop {
+                let other = candidates[rng.below(candidates.len())];
+                if other != orphan {
+                    break other;
+                }
+            };
+            (orphan, other)
+        },
+        (Some(orphan), None, None) => (orphan, candidates[rng.below(candidates.len())]),
+        (None, _, None) => (
+            candidates[rng.below(candidates.len())],
+            candidates[rng.below(candidates.len())],
+        ),
+        (None, _, Some(seen)) => {
+            let fresh = |&(low, high): &(usize, usize)| {
+                low != high && !seen.contains(&(variable_id, low, high))
+            };
+            let random = (0..PAIR_TRIES)
+                .map(|_| {
+                    (
+                        candidates[rng.below(candidates.len())],
+                        candidates[rng.below(candidates.len())],
+                    )
+                })
+                .find(fresh);
+            random
+                .or_else(|| {
+                    candidates
+                        .iter()
+                        .flat_map(|&low| candidates.iter().map(move |&high| (low, high)))
+                        .find(fresh)
+                })
+                .ok_or(EvaluationError(
+                    "Too many nodes for the number of variables",
+                ))?
+        },
+    };
+    // A node with an orphan child is unique whichever way round it is.
+    if first.is_some() && rng.chance(0.5) {
+        pair = (pair.1, pair.0);
+    }
+    Ok(pair)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::gen::{random_bdd, BddOptions, Order};
+    use crate::Evaluate;
+
+    fn options(variables: usize, nodes: usize, order: Order, reduced: bool) -> BddOptions {
+        BddOptions {
+            variables,
+            nodes,
+            order,
+            reduced,
+            seed: 3,
+        }
+    }
+
+    #[test]
+    fn exact_node_count() {
+        for order in [Order::Ascending, Order::Shuffled, Order::Unordered] {
+            for reduced in [true, false] {
+                let bdd = random_bdd(&options(6, 20, order, reduced)).unwrap();
+                assert_eq!(22, bdd.node_count());
+                assert!(bdd.truth_table().is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn reduced_ordered_is_canonical() {
+        let bdd = random_bdd(&options(8, 30, Order::Ascending, true)).unwrap();
+        let table = bdd.truth_table().unwrap();
+        let canonical = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+        assert_eq!(canonical.node_count(), bdd.node_count());
+    }
+
+    #[test]
+    fn reproducible() {
+        let first = random_bdd(&options(5, 9, Order::Shuffled, true)).unwrap();
+        let second = random_bdd(&options(5, 9, Order::Shuffled, true)).unwrap();
+        assert_eq!(first.to_string(), second.to_string());
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let bdd = random_bdd(&options(5, 12, Order::Unordered, false)).unwrap();
+        let parsed: BinaryDecisionDiagram = bdd.to_string().parse().unwrap();
+        assert_eq!(bdd.truth_table(), parsed.truth_table());
+    }
+
+    #[test]
+    fn too_many_nodes() {
+        assert!(random_bdd(&options(2, 4, Order::Ascending, true)).is_err());
+        assert!(random_bdd(&options(0, 1, Order::Ascending, true)).is_err());
+        assert!(random_bdd(&options(3, 0, Order::Ascending, true)).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::crossbar::{CrossbarMatrix, Defect, DefectMap, Literal, Wire};
+use crate::gen::Rng;
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+/// Share of programmed devices that are always on rather than a literal.
+const ALWAYS_ON: f64 = 0.1;
+
+/// Shape of a crossbar made by [`random_crossbar`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrossbarOptions {
+    pub rows: usize,
+    pub columns: usize,
+    /// number of variables literals are drawn from
+    pub variables: usize,
+    /// fraction of crossings with a device that is not permanently off
+    pub density: f64,
+    pub seed: u64,
+}
+
+impl Default for CrossbarOptions {
+    fn default() -> Self {
+        Self {
+            rows: 4,
+            columns: 4,
+            variables: 3,
+            density: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// A random crossbar with input on the bottom row and output on the top
+/// row. Each crossing holds a device with probability `options.density`;
+/// most devices are a random positive or negative literal and the rest are
+/// always on.
+/// # Errors
+/// * `EvaluationError` - the crossbar has no rows or columns, or the density is
+///   not between 0 and 1
+pub fn random_crossbar(options: &CrossbarOptions) -> Result<CrossbarMatrix, FlowError> {
+    let CrossbarOptions {
+        rows,
+        columns,
+        variables,
+        density,
+        seed,
+    } = *options;
+    if !(0.0..=1.0).contains(&density) {
+        return Err(EvaluationError("Density must be between 0 and 1"));
+    }
+    let mut crossbar = CrossbarMatrix::new(
+        rows,
+        columns,
+        Wire::Row(rows.saturating_sub(1)),
+        vec![Wire::Row(0)],
+    )
+    .map_err(|_| EvaluationError("A crossbar needs at least one row and column"))?;
+
+    let mut rng = Rng::new(seed);
+    for row in 0..rows {
+        for column in 0..columns {
+            if !rng.chance(density) {
+                continue;
+            }
+            let literal = if variables == 0 || rng.chance(ALWAYS_ON) {
+                Literal::On
+            } else if rng.chance(0.5) {
+                Literal::Positive(rng.below(variables))
+            } else {
+                Literal::Negative(rng.below(variables))
+            };
+            crossbar.set_cell(row, column, literal);
+        }
+    }
+    Ok(crossbar)
+}
+
+/// Shape and fault rates of a defect map made by [`random_defects`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DefectOptions {
+    pub rows: usize,
+    pub columns: usize,
+    /// fraction of cells stuck on
+    pub stuck_on: f64,
+    /// fraction of cells stuck off
+    pub stuck_off: f64,
+    pub seed: u64,
+}
+
+/// A random defect map, each cell independently stuck on, stuck off or
+/// working.
+/// # Errors
+/// * `EvaluationError` - the crossbar has no rows or columns, or the rates are
+///   negative or add up to more than 1
+pub fn random_defects(options: &DefectOptions) -> Result<DefectMap, FlowError> {
+    let DefectOptions {
+        rows,
+        columns,
+        stuck_on,
+        stuck_off,
+        seed,
+    } = *options;
+    if !(0.0..=1.0).contains(&stuck_on) || !(0.0..=1.0 - stuck_on).contains(&stuck_off) {
+        return Err(EvaluationError(
+            "Defect rates must add up to between 0 and 1",
+        ));
+    }
+    let mut defects = DefectMap::new(rows, columns)
+        .map_err(|_| EvaluationError("A crossbar needs at least one row and column"))?;
+
+    let mut rng = Rng::new(seed);
+    for row in 0..rows {
+        for column in 0..columns {
+            if rng.chance(stuck_on + stuck_off) {
+                let defect = if rng.chance(stuck_on / (stuck_on + stuck_off)) {
+                    Defect::StuckOn
+                } else {
+                    Defect::StuckOff
+                };
+                defects.set(row, column, Some(defect));
+            }
+        }
+    }
+    Ok(defects)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crossbar::{Defect, Wire};
+    use crate::gen::{random_crossbar, random_defects, CrossbarOptions, DefectOptions};
+
+    #[test]
+    fn shape() {
+        let crossbar = random_crossbar(&CrossbarOptions {
+            rows: 5,
+            columns: 7,
+            variables: 4,
+            density: 1.0,
+            seed: 9,
+        })
+        .unwrap();
+        assert_eq!((5, 7), (crossbar.rows(), crossbar.columns()));
+        assert_eq!(35, crossbar.device_count());
+        assert!(crossbar.variables() <= 4);
+        assert_eq!(Wire::Row(4), crossbar.input());
+    }
+
+    #[test]
+    fn empty_density() {
+        let options = CrossbarOptions {
+            density: 0.0,
+            ..CrossbarOptions::default()
+        };
+        assert_eq!(0, random_crossbar(&options).unwrap().device_count());
+    }
+
+    #[test]
+    fn invalid() {
+        let options = CrossbarOptions {
+            density: 1.5,
+            ..CrossbarOptions::default()
+        };
+        assert!(random_crossbar(&options).is_err());
+        let options = CrossbarOptions {
+            rows: 0,
+            ..CrossbarOptions::default()
+        };
+        assert!(random_crossbar(&options).is_err());
+    }
+
+    #[test]
+    fn defects() {
+        let options = DefectOptions {
+            rows: 10,
+            columns: 10,
+            stuck_on: 0.2,
+            stuck_off: 0.0,
+            seed: 4,
+        };
+        let defects = random_defects(&options).unwrap();
+        assert!(defects.count() > 0);
+        assert_eq!(defects, random_defects(&options).unwrap());
+        assert!((0..10)
+            .all(|row| (0..10).all(|column| defects.get(row, column) != Some(Defect::StuckOff))));
+        let options = DefectOptions {
+            stuck_off: 0.9,
+            ..options
+        };
+        assert!(random_defects(&options).is_err());
+    }
+}
@@ -20,7 +20,11 @@ use std::num::ParseIntError;
 
 use crate::FlowError::{EvaluationError, ParseError, VariableAssignmentError};
 
+pub mod add;
 pub mod bdd;
+pub mod crossbar;
+pub mod gen;
+pub mod zdd;
 
 pub type Variable = Option<bool>;
 
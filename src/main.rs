@@ -17,10 +17,13 @@
 use std::fmt::Debug;
 use std::io::Write;
 use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
 use std::{fs, io};
 
 use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
-use flow::bdd::BinaryDecisionDiagram;
+use flow::bdd::{AigerFormat, BddManager, BinaryDecisionDiagram, VerilogStyle};
+use flow::crossbar::{AnalogOptions, CrossbarMatrix, DefectMap, PathReport, RenderOptions};
+use flow::gen::{random_bdd, random_crossbar, BddOptions, CrossbarOptions, Order};
 use flow::{byte_to_bools, Evaluate, FlowError};
 
 #[derive(Debug, Parser)]
@@ -36,6 +39,29 @@ enum Action {
     Read(ReadArguments),
     /// evaluate logical artifact
     Evaluate(EvaluateArguments),
+    /// write the logical artifact to a file
+    Write(WriteArguments),
+    /// generate a random logical artifact and load it into memory
+    Generate(GenerateArguments),
+    /// report statistics about the logical artifact
+    Info,
+    /// simulate a crossbar matrix as a resistive network
+    Simulate(SimulateArguments),
+    /// shrink the loaded crossbar matrix to fewer rows and columns
+    Minimize,
+    /// draw the loaded crossbar matrix as a grid
+    Show(ShowArguments),
+    /// list the conducting paths of the loaded crossbar matrix and flag
+    /// sneak paths
+    Paths(PathsArguments),
+    /// compute a crossbar matrix output as a binary decision diagram
+    Extract(ExtractArguments),
+    /// synthesize binary decision diagrams onto one crossbar matrix
+    Synthesize(SynthesizeArguments),
+    /// place the loaded crossbar matrix onto a crossbar with defects
+    Map(DefectArguments),
+    /// show how defects change the loaded crossbar matrix's truth table
+    Defects(DefectArguments),
     /// exit the program
     Quit,
 }
@@ -47,6 +73,177 @@ struct ReadArguments {
     r#type: ArtifactType,
     /// The file to read from
     file: String,
+    /// Which output to load from a file with several outputs
+    #[arg(long, default_value_t = 0)]
+    output: usize,
+    /// Number of variables of a truth table, which a hex table must give
+    #[arg(long)]
+    variables: Option<usize>,
+}
+
+#[derive(Args, Debug)]
+struct WriteArguments {
+    /// The format to write in
+    #[arg(value_enum, required = true)]
+    format: ExportFormat,
+    /// The file to write to
+    file: String,
+    #[command(flatten)]
+    analog: AnalogArguments,
+    /// Assignment a SPICE netlist is set to, as a space-separated list of
+    /// bools; without one every variable is false
+    #[arg(short, long, value_delimiter = ' ', num_args = 1..)]
+    bools: Option<Vec<bool>>,
+}
+
+impl WriteArguments {
+    /// Reject the SPICE-only arguments for every other format.
+    fn check(&self) -> Result<(), &'static str> {
+        if self.format != ExportFormat::Spice && (self.bools.is_some() || self.analog.is_given()) {
+            return Err("Only write spice takes -b, --r-on, --r-off, --r-sense or --voltage");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args, Debug)]
+struct GenerateArguments {
+    /// The type of logical artifact to generate
+    #[arg(value_enum, required = true)]
+    r#type: GeneratedType,
+    /// Number of variables
+    #[arg(long, default_value_t = 4)]
+    variables: usize,
+    /// Number of decision nodes in a binary decision diagram
+    #[arg(long, default_value_t = 8)]
+    nodes: usize,
+    /// Variable order along the paths of a binary decision diagram
+    #[arg(long, value_enum, default_value_t = VariableOrder::Ascending)]
+    order: VariableOrder,
+    /// Allow redundant tests and duplicate nodes in a binary decision diagram
+    #[arg(long)]
+    unreduced: bool,
+    /// Number of rows in a crossbar matrix
+    #[arg(long, default_value_t = 4)]
+    rows: usize,
+    /// Number of columns in a crossbar matrix
+    #[arg(long, default_value_t = 4)]
+    columns: usize,
+    /// Fraction of crossbar cells holding a device
+    #[arg(long, default_value_t = 0.5)]
+    density: f64,
+    /// Seed for the generator; taken from the clock when not given
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+#[command(group(ArgGroup::new("report").required(true).multiple(true).args(["bools", "margins"])))]
+struct SimulateArguments {
+    #[command(flatten)]
+    analog: AnalogArguments,
+    /// Assignment to report the output readings for, as a space-separated list
+    /// of bools
+    #[arg(short, long, value_delimiter = ' ', num_args = 1..)]
+    bools: Option<Vec<bool>>,
+    /// Report the noise margins, which simulates every assignment
+    #[arg(long)]
+    margins: bool,
+}
+
+/// Electrical parameters shared by `simulate` and `write spice`. Each one left
+/// out takes its value from [`AnalogOptions::default`].
+#[derive(Args, Debug)]
+struct AnalogArguments {
+    /// Resistance of a conducting device, in ohms [default: 1000]
+    #[arg(long)]
+    r_on: Option<f64>,
+    /// Resistance of a device that does not conduct, in ohms [default: 1000000]
+    #[arg(long)]
+    r_off: Option<f64>,
+    /// Resistance from each output wire to ground, in ohms [default: 1000]
+    #[arg(long)]
+    r_sense: Option<f64>,
+    /// Voltage applied to the input wire, in volts [default: 1]
+    #[arg(long)]
+    voltage: Option<f64>,
+}
+
+impl AnalogArguments {
+    fn options(&self) -> AnalogOptions {
+        let default = AnalogOptions::default();
+        AnalogOptions {
+            r_on: self.r_on.unwrap_or(default.r_on),
+            r_off: self.r_off.unwrap_or(default.r_off),
+            r_sense: self.r_sense.unwrap_or(default.r_sense),
+            voltage: self.voltage.unwrap_or(default.voltage),
+        }
+    }
+
+    /// Whether any parameter was given.
+    fn is_given(&self) -> bool {
+        [self.r_on, self.r_off, self.r_sense, self.voltage]
+            .iter()
+            .any(Option::is_some)
+    }
+}
+
+#[derive(Args, Debug)]
+struct ShowArguments {
+    /// Draw with box-drawing characters instead of ASCII
+    #[arg(long)]
+    unicode: bool,
+    /// Assignment whose conducting path to an output is highlighted, as a
+    /// space-separated list of bools
+    #[arg(short, long, value_delimiter = ' ', num_args = 1..)]
+    bools: Option<Vec<bool>>,
+    /// Which output the highlighted path leads to
+    #[arg(long, default_value_t = 0)]
+    output: usize,
+    /// Also write the drawing to an SVG file
+    #[arg(long)]
+    svg: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct PathsArguments {
+    /// Assignment whose paths are listed, as a space-separated list of bools;
+    /// without one every assignment is summarised
+    #[arg(short, long, value_delimiter = ' ', num_args = 1..)]
+    bools: Option<Vec<bool>>,
+    /// Most paths to find for each output
+    #[arg(long, default_value_t = 1000)]
+    limit: usize,
+}
+
+#[derive(Args, Debug)]
+struct ExtractArguments {
+    /// Which output to compute
+    #[arg(long, default_value_t = 0)]
+    output: usize,
+    /// A binary decision diagram file to compare the output against instead
+    /// of loading the result
+    #[arg(long)]
+    spec: Option<String>,
+}
+
+#[derive(Args, Debug)]
+struct SynthesizeArguments {
+    /// An AIGER file whose outputs each get an output wire; without one the
+    /// loaded binary decision diagram is synthesized
+    #[arg(long)]
+    aiger: Option<String>,
+    /// Run the minimization pass on the synthesized crossbar, which checks
+    /// the result over the whole truth table
+    #[arg(long)]
+    minimize: bool,
+}
+
+#[derive(Args, Debug)]
+struct DefectArguments {
+    /// The defect map to read, one line per row of `.`, `0` (stuck off) or
+    /// `1` (stuck on)
+    file: String,
 }
 
 #[derive(Args, Debug)]
@@ -69,11 +266,85 @@ enum ArtifactType {
     /// apply action to crossbar matrix
     #[value(name = "xbar")]
     CrossbarMatrix,
+    /// read a DIMACS CNF formula into a binary decision diagram
+    #[value(name = "cnf")]
+    Dimacs,
+    /// read an AIGER and-inverter graph, ASCII or binary, into a binary
+    /// decision diagram
+    #[value(name = "aig")]
+    Aiger,
+    /// read a truth table into a binary decision diagram, either as `0`/`1`
+    /// entries in index order or as `0x`-prefixed hex
+    #[value(name = "table")]
+    TruthTable,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+enum GeneratedType {
+    /// generate a binary decision diagram
+    #[value(name = "bdd")]
+    BinaryDecisionDiagram,
+    /// generate a crossbar matrix
+    #[value(name = "xbar")]
+    CrossbarMatrix,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+enum VariableOrder {
+    /// every path tests variables in ascending order
+    Ascending,
+    /// every path follows the same random variable order
+    Shuffled,
+    /// each node tests a random variable
+    Unordered,
+}
+
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, ValueEnum)]
+enum ExportFormat {
+    /// binary decision diagram text format
+    #[value(name = "bdd")]
+    BinaryDecisionDiagram,
+    /// ASCII AIGER
+    #[value(name = "aag")]
+    AigerAscii,
+    /// binary AIGER
+    #[value(name = "aig")]
+    AigerBinary,
+    /// Verilog module with one multiplexer per decision node, or a behavioural
+    /// model of a crossbar matrix
+    #[value(name = "verilog")]
+    VerilogMuxTree,
+    /// Verilog module with a single assign expression
+    #[value(name = "verilog-expr")]
+    VerilogExpression,
+    /// crossbar matrix text format
+    #[value(name = "xbar")]
+    CrossbarMatrix,
+    /// SPICE netlist of a crossbar matrix for ngspice (experimental)
+    #[value(name = "spice")]
+    Spice,
+    /// SVG drawing of a crossbar matrix
+    #[value(name = "svg")]
+    Svg,
+}
+
+enum LogicalArtifact {
+    Bdd(BinaryDecisionDiagram),
+    Crossbar(CrossbarMatrix),
+}
+
+impl LogicalArtifact {
+    fn evaluate(&self) -> &dyn Evaluate {
+        match self {
+            Self::Bdd(bdd) => bdd,
+            Self::Crossbar(crossbar) => crossbar,
+        }
+    }
 }
 
 #[derive(Default)]
 struct ApplicationContext {
-    logical_artifact: Option<Box<dyn Evaluate>>,
+    logical_artifact: Option<LogicalArtifact>,
 }
 
 fn main() -> Result<(), String> {
@@ -135,19 +406,51 @@ fn respond(command: Cli, x: &mut ApplicationContext) -> Result<bool, String> {
                         })?;
                     bdd
                 },
+                ArtifactType::Dimacs => {
+                    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                    BinaryDecisionDiagram::from_dimacs(&source).map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?
+                },
+                ArtifactType::Aiger => {
+                    let source = fs::read(path).map_err(|e| e.to_string())?;
+                    BinaryDecisionDiagram::from_aiger(&source)
+                        .map_err(|e| match e {
+                            FlowError::EvaluationError(str)
+                            | FlowError::ParseError(str)
+                            | FlowError::VariableAssignmentError(str) => str,
+                        })?
+                        .into_iter()
+                        .nth(args.output)
+                        .ok_or("The file does not have that many outputs.")?
+                },
+                ArtifactType::TruthTable => {
+                    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                    read_truth_table(source.trim(), args.variables).map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?
+                },
                 ArtifactType::CrossbarMatrix => {
-                    todo!()
+                    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+                    let crossbar = CrossbarMatrix::parse(&source).map_err(|e| e.to_string())?;
+                    x.logical_artifact = Some(LogicalArtifact::Crossbar(crossbar));
+                    return Ok(false);
                 },
             };
-            x.logical_artifact = Some(Box::new(eval));
+            x.logical_artifact = Some(LogicalArtifact::Bdd(eval));
 
             Ok(false)
         },
         Action::Evaluate(args) => {
-            let artifact = x
+            let loaded = x
                 .logical_artifact
-                .as_mut()
+                .as_ref()
                 .ok_or("Must read in a logical artifact.")?;
+            let artifact = loaded.evaluate();
             let bools: Vec<bool> = match args.hex {
                 Some(hex) => (0..hex.len())
                     .step_by(2)
@@ -174,6 +477,392 @@ fn respond(command: Cli, x: &mut ApplicationContext) -> Result<bool, String> {
                 .join(", ");
             println!("{output}");
             println!("Evaluation: {result}");
+            if let LogicalArtifact::Crossbar(crossbar) = loaded {
+                if crossbar.outputs().len() > 1 {
+                    let outputs = crossbar.eval_outputs(&bools).map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?;
+                    let outputs: Vec<String> = outputs.iter().map(ToString::to_string).collect();
+                    println!("Outputs: {}", outputs.join(" "));
+                }
+            }
+            if let (LogicalArtifact::Crossbar(crossbar), true) = (loaded, result) {
+                let path = crossbar
+                    .trace(&bools, 0)
+                    .map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?
+                    .unwrap_or_default();
+                let wires: Vec<String> = path.iter().map(ToString::to_string).collect();
+                println!("Path: {}", wires.join(" "));
+            }
+
+            Ok(false)
+        },
+        Action::Write(args) => {
+            args.check()?;
+            let artifact = x
+                .logical_artifact
+                .as_ref()
+                .ok_or("Must read in a logical artifact.")?;
+            let contents: Vec<u8> = match (args.format, artifact) {
+                (ExportFormat::BinaryDecisionDiagram, LogicalArtifact::Bdd(bdd)) => {
+                    Ok(format!("{bdd}\n").into_bytes())
+                },
+                (ExportFormat::AigerAscii, LogicalArtifact::Bdd(bdd)) => {
+                    BinaryDecisionDiagram::to_aiger(std::slice::from_ref(bdd), AigerFormat::Ascii)
+                },
+                (ExportFormat::AigerBinary, LogicalArtifact::Bdd(bdd)) => {
+                    BinaryDecisionDiagram::to_aiger(std::slice::from_ref(bdd), AigerFormat::Binary)
+                },
+                (ExportFormat::VerilogMuxTree, LogicalArtifact::Bdd(bdd)) => bdd
+                    .to_verilog("bdd", VerilogStyle::MuxTree)
+                    .map(String::into_bytes),
+                (ExportFormat::VerilogExpression, LogicalArtifact::Bdd(bdd)) => bdd
+                    .to_verilog("bdd", VerilogStyle::Expression)
+                    .map(String::into_bytes),
+                (ExportFormat::CrossbarMatrix, LogicalArtifact::Crossbar(crossbar)) => {
+                    Ok(format!("{crossbar}\n").into_bytes())
+                },
+                (ExportFormat::VerilogMuxTree, LogicalArtifact::Crossbar(crossbar)) => {
+                    crossbar.to_verilog("xbar").map(String::into_bytes)
+                },
+                (ExportFormat::Spice, LogicalArtifact::Crossbar(crossbar)) => crossbar
+                    .to_spice(args.bools.as_deref(), &args.analog.options())
+                    .map(String::into_bytes),
+                (ExportFormat::Svg, LogicalArtifact::Crossbar(crossbar)) => {
+                    Ok(crossbar.to_svg(None).into_bytes())
+                },
+                (
+                    ExportFormat::CrossbarMatrix | ExportFormat::Spice | ExportFormat::Svg,
+                    LogicalArtifact::Bdd(_),
+                ) => Err(FlowError::EvaluationError(
+                    "Only a crossbar matrix can be written in that format",
+                )),
+                (_, LogicalArtifact::Crossbar(_)) => Err(FlowError::EvaluationError(
+                    "A crossbar matrix can only be written as xbar, verilog, spice or svg",
+                )),
+            }
+            .map_err(|e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            })?;
+            fs::write(Path::new(args.file.as_str()), contents).map_err(|e| e.to_string())?;
+
+            Ok(false)
+        },
+        Action::Generate(args) => {
+            let seed = args.seed.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |time| time.as_secs())
+            });
+            match args.r#type {
+                GeneratedType::BinaryDecisionDiagram => {
+                    let order = match args.order {
+                        VariableOrder::Ascending => Order::Ascending,
+                        VariableOrder::Shuffled => Order::Shuffled,
+                        VariableOrder::Unordered => Order::Unordered,
+                    };
+                    let bdd = random_bdd(&BddOptions {
+                        variables: args.variables,
+                        nodes: args.nodes,
+                        order,
+                        reduced: !args.unreduced,
+                        seed,
+                    })
+                    .map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?;
+                    x.logical_artifact = Some(LogicalArtifact::Bdd(bdd));
+                },
+                GeneratedType::CrossbarMatrix => {
+                    let crossbar = random_crossbar(&CrossbarOptions {
+                        rows: args.rows,
+                        columns: args.columns,
+                        variables: args.variables,
+                        density: args.density,
+                        seed,
+                    })
+                    .map_err(|e| match e {
+                        FlowError::EvaluationError(str)
+                        | FlowError::ParseError(str)
+                        | FlowError::VariableAssignmentError(str) => str,
+                    })?;
+                    print_crossbar_info(&crossbar);
+                    x.logical_artifact = Some(LogicalArtifact::Crossbar(crossbar));
+                },
+            }
+            println!("Seed: {seed}");
+
+            Ok(false)
+        },
+        Action::Info => {
+            let artifact = x
+                .logical_artifact
+                .as_ref()
+                .ok_or("Must read in a logical artifact.")?;
+            match artifact {
+                LogicalArtifact::Bdd(bdd) => println!("{}", bdd.statistics()),
+                LogicalArtifact::Crossbar(crossbar) => print_crossbar_info(crossbar),
+            }
+
+            Ok(false)
+        },
+        Action::Simulate(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let options = args.analog.options();
+            let to_str = |e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            };
+            if let Some(bools) = args.bools {
+                let readings = crossbar.simulate(&bools, &options).map_err(to_str)?;
+                for (output, reading) in crossbar.outputs().iter().zip(readings) {
+                    println!(
+                        "Output {output}: {:.6} V, {:.6e} A",
+                        reading.voltage, reading.current
+                    );
+                }
+            }
+            if !args.margins {
+                return Ok(false);
+            }
+            let margins = crossbar.noise_margins(&options).map_err(to_str)?;
+            for (output, margin) in crossbar.outputs().iter().zip(margins) {
+                let volts =
+                    |value: Option<f64>| value.map_or("-".to_string(), |v| format!("{v:.6} V"));
+                println!(
+                    "Output {output}: lowest true {}, highest false {}, noise margin {}",
+                    volts(margin.lowest_true),
+                    volts(margin.highest_false),
+                    volts(margin.margin())
+                );
+            }
+
+            Ok(false)
+        },
+        Action::Minimize => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let minimized = crossbar.minimize().map_err(|e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            })?;
+            print_area_change(crossbar, &minimized);
+            x.logical_artifact = Some(LogicalArtifact::Crossbar(minimized));
+
+            Ok(false)
+        },
+        Action::Show(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let path = match args.bools {
+                Some(bools) => crossbar.trace(&bools, args.output).map_err(|e| match e {
+                    FlowError::EvaluationError(str)
+                    | FlowError::ParseError(str)
+                    | FlowError::VariableAssignmentError(str) => str,
+                })?,
+                None => None,
+            };
+            let options = RenderOptions {
+                unicode: args.unicode,
+                path: path.as_deref(),
+            };
+            println!("{}", crossbar.grid(options));
+            if let Some(file) = args.svg {
+                fs::write(file, crossbar.to_svg(path.as_deref())).map_err(|e| e.to_string())?;
+            }
+
+            Ok(false)
+        },
+        Action::Paths(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let to_str = |e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            };
+            let summary = |report: &PathReport| {
+                let at_least = if report.truncated { "at least " } else { "" };
+                match report.shortest {
+                    None => "no conducting path".to_string(),
+                    Some(shortest) => format!(
+                        "{at_least}{} path{}, shortest {shortest}, {at_least}{} sneak",
+                        report.paths.len(),
+                        if report.paths.len() == 1 { "" } else { "s" },
+                        report.sneak_count()
+                    ),
+                }
+            };
+            if let Some(bools) = args.bools {
+                let reports = crossbar
+                    .conducting_paths(&bools, args.limit)
+                    .map_err(to_str)?;
+                for (output, report) in crossbar.outputs().iter().zip(reports) {
+                    println!("Output {output}: {}", summary(&report));
+                    for path in &report.paths {
+                        let wires: Vec<String> =
+                            path.wires.iter().map(ToString::to_string).collect();
+                        let sneak = if path.sneak { " (sneak)" } else { "" };
+                        println!("  {}{sneak}", wires.join(" "));
+                    }
+                }
+            } else {
+                let variables = crossbar.variables();
+                let combinations = u32::try_from(variables)
+                    .ok()
+                    .and_then(|variables| 1usize.checked_shl(variables))
+                    .ok_or("Too many variables to summarise every assignment.")?;
+                for var_set in 0..combinations {
+                    let bools: Vec<bool> =
+                        (0..variables).map(|id| var_set >> id & 1 == 1).collect();
+                    let reports = crossbar
+                        .conducting_paths(&bools, args.limit)
+                        .map_err(to_str)?;
+                    let outputs: Vec<String> = crossbar
+                        .outputs()
+                        .iter()
+                        .zip(&reports)
+                        .map(|(output, report)| format!("{output} {}", summary(report)))
+                        .collect();
+                    let bits: String = bools.iter().map(|&b| if b { '1' } else { '0' }).collect();
+                    println!("{bits}: {}", outputs.join("; "));
+                }
+            }
+
+            Ok(false)
+        },
+        Action::Extract(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let to_str = |e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            };
+            let spec: Option<BinaryDecisionDiagram> = match &args.spec {
+                Some(file) => Some(
+                    fs::read_to_string(file)
+                        .map_err(|e| e.to_string())?
+                        .parse()
+                        .map_err(to_str)?,
+                ),
+                None => None,
+            };
+            let variables = spec
+                .as_ref()
+                .map_or(0, BinaryDecisionDiagram::variables)
+                .max(crossbar.variables());
+            let manager = BddManager::new(variables);
+            let output = crossbar
+                .to_bdds(&manager)
+                .map_err(to_str)?
+                .into_iter()
+                .nth(args.output)
+                .ok_or("The crossbar does not have that many outputs.")?;
+            if let Some(spec) = spec {
+                if manager.import(&spec).map_err(to_str)? == output {
+                    println!("The output matches the specification.");
+                } else {
+                    println!("The output differs from the specification.");
+                }
+            } else {
+                let bdd = output.to_diagram();
+                println!("Nodes: {}", bdd.node_count());
+                x.logical_artifact = Some(LogicalArtifact::Bdd(bdd));
+            }
+
+            Ok(false)
+        },
+        Action::Synthesize(args) => {
+            let to_str = |e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            };
+            let diagrams = match (args.aiger, &x.logical_artifact) {
+                (Some(file), _) => {
+                    let source = fs::read(file).map_err(|e| e.to_string())?;
+                    BinaryDecisionDiagram::from_aiger(&source).map_err(to_str)?
+                },
+                (None, Some(LogicalArtifact::Bdd(bdd))) => vec![bdd.clone()],
+                (None, _) => return Err("Must read in a binary decision diagram.".to_string()),
+            };
+            let mut crossbar = BinaryDecisionDiagram::to_crossbar(&diagrams).map_err(to_str)?;
+            if args.minimize {
+                let minimized = crossbar.minimize().map_err(to_str)?;
+                print_area_change(&crossbar, &minimized);
+                crossbar = minimized;
+            }
+            print_crossbar_info(&crossbar);
+            x.logical_artifact = Some(LogicalArtifact::Crossbar(crossbar));
+
+            Ok(false)
+        },
+        Action::Map(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let defects = read_defects(&args.file)?;
+            let mapping = crossbar.map_onto(&defects).map_err(|e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            })?;
+            let join = |indices: &[usize]| {
+                indices
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+            println!("Rows: {}", join(&mapping.rows));
+            println!("Columns: {}", join(&mapping.columns));
+            x.logical_artifact = Some(LogicalArtifact::Crossbar(mapping.crossbar));
+
+            Ok(false)
+        },
+        Action::Defects(args) => {
+            let Some(LogicalArtifact::Crossbar(crossbar)) = &x.logical_artifact else {
+                return Err("Must read in a crossbar matrix.".to_string());
+            };
+            let defects = read_defects(&args.file)?;
+            let changes = crossbar.defect_changes(&defects).map_err(|e| match e {
+                FlowError::EvaluationError(str)
+                | FlowError::ParseError(str)
+                | FlowError::VariableAssignmentError(str) => str,
+            })?;
+            let bits = |bools: &[bool]| -> String {
+                bools.iter().map(|&b| if b { '1' } else { '0' }).collect()
+            };
+            if changes.is_empty() {
+                println!("The defects do not change the truth table.");
+            }
+            for change in changes {
+                println!(
+                    "{}: {} -> {}",
+                    bits(&change.values),
+                    bits(&change.expected),
+                    bits(&change.actual)
+                );
+            }
 
             Ok(false)
         },
@@ -181,12 +870,147 @@ fn respond(command: Cli, x: &mut ApplicationContext) -> Result<bool, String> {
     }
 }
 
+fn read_defects(path: &str) -> Result<DefectMap, String> {
+    let source = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    DefectMap::parse(&source).map_err(|e| e.to_string())
+}
+
+fn print_crossbar_info(crossbar: &CrossbarMatrix) {
+    println!("Dimensions: {}x{}", crossbar.rows(), crossbar.columns());
+    println!("Devices: {}", crossbar.device_count());
+    println!("Density: {:.3}", crossbar.density());
+}
+
+fn print_area_change(before: &CrossbarMatrix, after: &CrossbarMatrix) {
+    println!(
+        "Area: {} ({}x{}) -> {} ({}x{})",
+        before.area(),
+        before.rows(),
+        before.columns(),
+        after.area(),
+        after.rows(),
+        after.columns()
+    );
+}
+
+fn read_truth_table(
+    source: &str,
+    variables: Option<usize>,
+) -> Result<BinaryDecisionDiagram, FlowError> {
+    if let Some(hex) = source.strip_prefix("0x") {
+        // The digits alone cannot say how many variables there are: `0x2`
+        // is x0 with one variable and x0 & !x1 with two.
+        let variables = variables.ok_or(FlowError::ParseError(
+            "A hex truth table needs its number of variables",
+        ))?;
+        return BinaryDecisionDiagram::from_hex_truth_table(hex, variables);
+    }
+    let table = source
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace())
+        .map(|c| match c {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(FlowError::ParseError("Truth table entries must be 0 or 1")),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let bdd = BinaryDecisionDiagram::from_truth_table(&table)?;
+    if variables.is_some_and(|variables| variables != bdd.variables()) {
+        return Err(FlowError::ParseError(
+            "The truth table has a different number of variables",
+        ));
+    }
+    Ok(bdd)
+}
+
 #[cfg(test)]
 mod test {
     use clap::CommandFactory;
+    use flow::crossbar::AnalogOptions;
+    use flow::Evaluate;
 
-    use crate::Cli;
+    use crate::{parse_command, read_truth_table, Action, Cli};
 
     #[test]
     fn verify_cmd() { Cli::command().debug_assert(); }
+
+    #[test]
+    fn write_spice_arguments() {
+        let line = "write spice out.cir -b true false --r-on 500 --voltage 0.5";
+        let Some(Cli {
+            action: Action::Write(args),
+        }) = parse_command(line)
+        else {
+            panic!("{line} did not parse as a write");
+        };
+        assert_eq!(Some(vec![true, false]), args.bools);
+        let options = args.analog.options();
+        assert_eq!(500.0, options.r_on);
+        assert_eq!(0.5, options.voltage);
+        assert_eq!(AnalogOptions::default().r_off, options.r_off);
+    }
+
+    #[test]
+    fn write_rejects_spice_arguments() {
+        for line in [
+            "write verilog out.v -b true",
+            "write bdd out.bdd --r-on 500",
+            "write svg out.svg --voltage 2",
+        ] {
+            let Some(Cli {
+                action: Action::Write(args),
+            }) = parse_command(line)
+            else {
+                panic!("{line} did not parse as a write");
+            };
+            assert!(args.check().is_err(), "{line}");
+        }
+        let Some(Cli {
+            action: Action::Write(args),
+        }) = parse_command("write svg out.svg")
+        else {
+            panic!("write svg did not parse");
+        };
+        assert_eq!(Ok(()), args.check());
+    }
+
+    #[test]
+    fn simulate_needs_a_report() {
+        assert!(parse_command("simulate").is_none());
+        assert!(parse_command("simulate --r-on 500").is_none());
+        let Some(Cli {
+            action: Action::Simulate(args),
+        }) = parse_command("simulate -b true")
+        else {
+            panic!("simulate -b did not parse");
+        };
+        assert!(!args.margins);
+        assert!(parse_command("simulate --margins").is_some());
+        assert!(parse_command("simulate -b true --margins").is_some());
+    }
+
+    #[test]
+    fn hex_truth_table() {
+        assert!(read_truth_table("0x2", None).is_err());
+        let identity = read_truth_table("0x2", Some(1)).unwrap();
+        assert_eq!(Ok(vec![false, true]), identity.truth_table());
+        let and_not = read_truth_table("0x2", Some(2)).unwrap();
+        assert_eq!(Ok(vec![false, true, false, false]), and_not.truth_table());
+        let constant = read_truth_table("0x1", Some(0)).unwrap();
+        assert_eq!(Ok(vec![true]), constant.truth_table());
+        assert!(read_truth_table("0x2", Some(0)).is_err());
+    }
+
+    #[test]
+    fn binary_truth_table() {
+        assert_eq!(
+            Ok(vec![false]),
+            read_truth_table("0", None).unwrap().truth_table()
+        );
+        assert_eq!(
+            Ok(vec![false, true]),
+            read_truth_table("01", Some(1)).unwrap().truth_table()
+        );
+        assert!(read_truth_table("01", Some(2)).is_err());
+    }
 }
@@ -0,0 +1,158 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::bdd::builder::{DiagramBuilder, FALSE_NODE};
+use crate::bdd::BinaryDecisionDiagram;
+use crate::zdd::{ZddNode, ZeroSuppressedDecisionDiagram, BASE, EMPTY};
+use crate::FlowError;
+
+impl TryFrom<&BinaryDecisionDiagram> for ZeroSuppressedDecisionDiagram {
+    type Error = FlowError;
+
+    /// The family of sets whose characteristic vectors satisfy the diagram.
+    fn try_from(bdd: &BinaryDecisionDiagram) -> Result<Self, Self::Error> {
+        let mut builder = DiagramBuilder::new();
+        let root = builder.import(bdd)?;
+        let variables = builder.build(root, bdd.variables()).variables();
+
+        let mut zdd = Self::with_terminals(variables);
+        zdd.root = from_bdd_node(&mut zdd, &builder, root, 0, &mut HashMap::new());
+        Ok(zdd)
+    }
+}
+
+/// Translate the sub-function at `id` over the variables from `level` up.
+fn from_bdd_node(
+    zdd: &mut ZeroSuppressedDecisionDiagram,
+    builder: &DiagramBuilder,
+    id: usize,
+    level: usize,
+    cache: &mut HashMap<(usize, usize), usize>,
+) -> usize {
+    if level == zdd.variables {
+        return if id == FALSE_NODE { EMPTY } else { BASE };
+    }
+    if let Some(&zdd_id) = cache.get(&(id, level)) {
+        return zdd_id;
+    }
+    let zdd_id = match builder.decision(id) {
+        Some((variable_id, low, high)) if variable_id == level => {
+            let low = from_bdd_node(zdd, builder, low, level + 1, cache);
+            let high = from_bdd_node(zdd, builder, high, level + 1, cache);
+            zdd.mk(level, low, high)
+        },
+        // The function does not depend on this variable, so each set appears
+        // both with and without it.
+        _ => {
+            let child = from_bdd_node(zdd, builder, id, level + 1, cache);
+            zdd.mk(level, child, child)
+        },
+    };
+    cache.insert((id, level), zdd_id);
+    zdd_id
+}
+
+impl From<&ZeroSuppressedDecisionDiagram> for BinaryDecisionDiagram {
+    /// The characteristic function of the family.
+    fn from(zdd: &ZeroSuppressedDecisionDiagram) -> Self {
+        let variables = zdd.variables.max(zdd.max_variable());
+        let mut builder = DiagramBuilder::new();
+        let root = to_bdd_node(
+            zdd,
+            &mut builder,
+            zdd.root,
+            0,
+            variables,
+            &mut HashMap::new(),
+        );
+        builder.build(root, variables)
+    }
+}
+
+/// Translate the family at `id` into a function of the variables from `level`
+/// up.
+fn to_bdd_node(
+    zdd: &ZeroSuppressedDecisionDiagram,
+    builder: &mut DiagramBuilder,
+    id: usize,
+    level: usize,
+    variables: usize,
+    cache: &mut HashMap<(usize, usize), usize>,
+) -> usize {
+    if level == variables {
+        return DiagramBuilder::constant(id == BASE);
+    }
+    if let Some(&bdd_id) = cache.get(&(id, level)) {
+        return bdd_id;
+    }
+    let bdd_id = match &zdd.nodes[id] {
+        ZddNode::Decision(node) if node.variable_id == level => {
+            let low = to_bdd_node(zdd, builder, node.low, level + 1, variables, cache);
+            let high = to_bdd_node(zdd, builder, node.high, level + 1, variables, cache);
+            builder.mk(level, low, high)
+        },
+        // A suppressed variable never appears in any set below this node.
+        _ => {
+            let low = to_bdd_node(zdd, builder, id, level + 1, variables, cache);
+            builder.mk(level, low, FALSE_NODE)
+        },
+    };
+    cache.insert((id, level), bdd_id);
+    bdd_id
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bdd::BinaryDecisionDiagram;
+    use crate::zdd::ZeroSuppressedDecisionDiagram;
+    use crate::Evaluate;
+
+    #[test]
+    fn from_bdd() {
+        let bdd = BinaryDecisionDiagram::from_truth_table(&[false, true, true, false]).unwrap();
+        let zdd = ZeroSuppressedDecisionDiagram::try_from(&bdd).unwrap();
+        assert_eq!(vec![vec![0], vec![1]], zdd.sets());
+    }
+
+    #[test]
+    fn from_bdd_unused_variable() {
+        let bdd = BinaryDecisionDiagram::variable(1, 2);
+        let zdd = ZeroSuppressedDecisionDiagram::try_from(&bdd).unwrap();
+        assert_eq!(vec![vec![0, 1], vec![1]], zdd.sets());
+    }
+
+    #[test]
+    fn to_bdd() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![], vec![0, 2]], 3);
+        let bdd = BinaryDecisionDiagram::from(&zdd);
+        let expected: Vec<bool> = (0..8).map(|i| i == 0 || i == 5).collect();
+        assert_eq!(expected, bdd.truth_table().unwrap());
+    }
+
+    #[test]
+    fn round_trip() {
+        let table: Vec<bool> = (0..16usize).map(|i| i.count_ones() == 2).collect();
+        let bdd = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+        let zdd = ZeroSuppressedDecisionDiagram::try_from(&bdd).unwrap();
+        assert_eq!(Ok(6), zdd.count());
+        assert_eq!(
+            table,
+            BinaryDecisionDiagram::from(&zdd).truth_table().unwrap()
+        );
+    }
+}
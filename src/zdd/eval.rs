@@ -0,0 +1,91 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::zdd::{ZddNode, ZeroSuppressedDecisionDiagram};
+use crate::FlowError::{EvaluationError, VariableAssignmentError};
+use crate::{convert_bits_to_bools, Evaluate, FlowError};
+
+impl Evaluate for ZeroSuppressedDecisionDiagram {
+    /// Whether the set of variables assigned true is a member of the family.
+    fn eval(&self, values: &[bool]) -> Result<bool, FlowError> {
+        if values.len() < self.variables {
+            return Err(VariableAssignmentError(
+                "The length of values is less than the number of variables to assign.",
+            ));
+        }
+        let mut id = self.root;
+        let mut next_variable = 0;
+        loop {
+            let top = self.top_variable(id).min(values.len());
+            // Variables skipped by the diagram are absent from every set.
+            if values[next_variable.min(top)..top]
+                .iter()
+                .any(|&value| value)
+            {
+                return Ok(false);
+            }
+            match &self.nodes[id] {
+                ZddNode::Terminal(value) => return Ok(*value),
+                ZddNode::Decision(node) => {
+                    let value = *values
+                        .get(node.variable_id)
+                        .ok_or(EvaluationError("Diagram tests an unassigned variable"))?;
+                    id = if value { node.high } else { node.low };
+                    next_variable = node.variable_id + 1;
+                },
+            }
+        }
+    }
+
+    fn truth_table(&self) -> Result<Vec<bool>, FlowError> {
+        let combinations = u32::try_from(self.variables)
+            .ok()
+            .and_then(|variables| 1usize.checked_shl(variables))
+            .ok_or(EvaluationError("Too many variables"))?;
+        let mut results: Vec<bool> = Vec::new();
+
+        for var_set in 0..combinations {
+            let vars = convert_bits_to_bools(var_set, self.variables);
+            results.push(self.eval(&vars)?);
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zdd::ZeroSuppressedDecisionDiagram;
+    use crate::Evaluate;
+
+    #[test]
+    fn member() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![0, 2]], 3);
+        assert_eq!(Ok(true), zdd.eval(&[true, false, true]));
+    }
+
+    #[test]
+    fn suppressed_variable_set() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![0, 2]], 3);
+        assert_eq!(Ok(false), zdd.eval(&[true, true, true]));
+    }
+
+    #[test]
+    fn truth_table() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![], vec![1]], 2);
+        assert_eq!(vec![true, false, true, false], zdd.truth_table().unwrap());
+    }
+}
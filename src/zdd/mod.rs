@@ -0,0 +1,248 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+/// A family of sets of variables, stored as a zero-suppressed decision
+/// diagram. A node's high edge adds its variable to every set below it, and a
+/// node whose high edge leads to the empty family is never created.
+#[derive(Clone, Debug)]
+pub struct ZeroSuppressedDecisionDiagram {
+    variables: usize,
+    nodes: Vec<ZddNode>,
+    unique: HashMap<(usize, usize, usize), usize>,
+    root: usize,
+}
+
+mod convert;
+mod eval;
+mod ops;
+
+/// Id of the terminal for the empty family.
+const EMPTY: usize = 0;
+/// Id of the terminal for the family holding only the empty set.
+const BASE: usize = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+enum ZddNode {
+    Decision(DecisionNode),
+    Terminal(bool),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct DecisionNode {
+    variable_id: usize,
+    low: usize,
+    high: usize,
+}
+
+impl ZeroSuppressedDecisionDiagram {
+    fn with_terminals(variables: usize) -> Self {
+        Self {
+            variables,
+            nodes: vec![ZddNode::Terminal(false), ZddNode::Terminal(true)],
+            unique: HashMap::new(),
+            root: EMPTY,
+        }
+    }
+
+    /// The family with no sets.
+    #[must_use]
+    pub fn empty(variables: usize) -> Self { Self::with_terminals(variables) }
+
+    /// The family holding only the empty set.
+    #[must_use]
+    pub fn base(variables: usize) -> Self {
+        let mut zdd = Self::with_terminals(variables);
+        zdd.root = BASE;
+        zdd
+    }
+
+    /// The family holding exactly the given sets of variable ids.
+    /// # Example
+    /// ```
+    /// use flow::zdd::ZeroSuppressedDecisionDiagram;
+    /// let family = ZeroSuppressedDecisionDiagram::from_sets(&[vec![0, 2], vec![1]], 3);
+    /// assert_eq!(2, family.count().unwrap());
+    /// ```
+    #[must_use]
+    pub fn from_sets(sets: &[Vec<usize>], variables: usize) -> Self {
+        let mut zdd = Self::with_terminals(variables);
+        let mut root = EMPTY;
+        for set in sets {
+            let mut set = set.clone();
+            set.sort_unstable();
+            set.dedup();
+            let mut node = BASE;
+            for &variable_id in set.iter().rev() {
+                node = zdd.mk(variable_id, EMPTY, node);
+            }
+            root = zdd.union_nodes(root, node, &mut HashMap::new());
+        }
+        zdd.variables = zdd.variables.max(zdd.max_variable());
+        zdd.root = root;
+        zdd
+    }
+
+    /// The number of variables the sets are drawn from.
+    #[must_use]
+    pub fn variables(&self) -> usize { self.variables }
+
+    /// The number of nodes, including both terminals.
+    #[must_use]
+    pub fn node_count(&self) -> usize {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![self.root, EMPTY, BASE];
+        let mut count = 0;
+        while let Some(id) = stack.pop() {
+            if seen[id] {
+                continue;
+            }
+            seen[id] = true;
+            count += 1;
+            if let ZddNode::Decision(node) = &self.nodes[id] {
+                stack.push(node.low);
+                stack.push(node.high);
+            }
+        }
+        count
+    }
+
+    /// Every set in the family as a sorted list of variable ids, in
+    /// lexicographic order. The list can be exponentially larger than the
+    /// diagram.
+    #[must_use]
+    pub fn sets(&self) -> Vec<Vec<usize>> {
+        let mut sets = Vec::new();
+        let mut stack = vec![(self.root, Vec::new())];
+        while let Some((id, set)) = stack.pop() {
+            match &self.nodes[id] {
+                ZddNode::Terminal(true) => sets.push(set),
+                ZddNode::Terminal(false) => {},
+                ZddNode::Decision(node) => {
+                    let mut with = set.clone();
+                    with.push(node.variable_id);
+                    stack.push((node.low, set));
+                    stack.push((node.high, with));
+                },
+            }
+        }
+        sets.sort();
+        sets
+    }
+
+    /// Find or create the node `(variable, low, high)`, applying the
+    /// zero-suppression rule.
+    fn mk(&mut self, variable_id: usize, low: usize, high: usize) -> usize {
+        if high == EMPTY {
+            return low;
+        }
+        if let Some(&id) = self.unique.get(&(variable_id, low, high)) {
+            return id;
+        }
+        let id = self.nodes.len();
+        self.nodes.push(ZddNode::Decision(DecisionNode {
+            variable_id,
+            low,
+            high,
+        }));
+        self.unique.insert((variable_id, low, high), id);
+        id
+    }
+
+    fn top_variable(&self, id: usize) -> usize {
+        match &self.nodes[id] {
+            ZddNode::Decision(node) => node.variable_id,
+            ZddNode::Terminal(_) => usize::MAX,
+        }
+    }
+
+    fn children(&self, id: usize) -> (usize, usize) {
+        match &self.nodes[id] {
+            ZddNode::Decision(node) => (node.low, node.high),
+            ZddNode::Terminal(_) => (id, EMPTY),
+        }
+    }
+
+    fn max_variable(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter_map(|node| match node {
+                ZddNode::Decision(node) => Some(node.variable_id + 1),
+                ZddNode::Terminal(_) => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Copy the nodes of `other` reachable from `id` into this diagram.
+    fn import(&mut self, other: &Self, id: usize, imported: &mut HashMap<usize, usize>) -> usize {
+        if id == EMPTY || id == BASE {
+            return id;
+        }
+        if let Some(&new_id) = imported.get(&id) {
+            return new_id;
+        }
+        let (variable_id, low, high) = match &other.nodes[id] {
+            ZddNode::Decision(node) => (node.variable_id, node.low, node.high),
+            ZddNode::Terminal(_) => unreachable!("terminals are handled above"),
+        };
+        let low = self.import(other, low, imported);
+        let high = self.import(other, high, imported);
+        let new_id = self.mk(variable_id, low, high);
+        imported.insert(id, new_id);
+        new_id
+    }
+
+    /// A copy of this diagram rooted at `root`, without unreachable nodes.
+    fn rooted_at(&self, root: usize) -> Self {
+        let mut zdd = Self::with_terminals(self.variables);
+        zdd.root = zdd.import(self, root, &mut HashMap::new());
+        zdd
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zdd::{ZeroSuppressedDecisionDiagram, BASE, EMPTY};
+
+    #[test]
+    fn zero_suppression() {
+        let mut zdd = ZeroSuppressedDecisionDiagram::empty(1);
+        assert_eq!(BASE, zdd.mk(0, BASE, EMPTY));
+    }
+
+    #[test]
+    fn shares_nodes() {
+        let mut zdd = ZeroSuppressedDecisionDiagram::empty(1);
+        let first = zdd.mk(0, EMPTY, BASE);
+        let second = zdd.mk(0, EMPTY, BASE);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn from_sets() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![0], vec![1], vec![0, 1]], 2);
+        assert_eq!(Ok(3), zdd.count());
+        assert_eq!(5, zdd.node_count());
+    }
+
+    #[test]
+    fn from_sets_duplicates() {
+        let zdd = ZeroSuppressedDecisionDiagram::from_sets(&[vec![1, 0, 1], vec![0, 1]], 2);
+        assert_eq!(Ok(1), zdd.count());
+    }
+}
@@ -0,0 +1,293 @@
+/*
+ * Copyright (c) 2024 William Nicholas Chitty
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use std::collections::HashMap;
+
+use crate::zdd::{ZddNode, ZeroSuppressedDecisionDiagram, EMPTY};
+use crate::FlowError;
+use crate::FlowError::EvaluationError;
+
+type Cache = HashMap<(usize, usize), usize>;
+
+impl ZeroSuppressedDecisionDiagram {
+    /// Sets in either family.
+    #[must_use]
+    pub fn union(&self, other: &Self) -> Self { self.combine(other, Self::union_nodes) }
+
+    /// Sets in both families.
+    #[must_use]
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.combine(other, Self::intersection_nodes)
+    }
+
+    /// Sets in this family but not in `other`.
+    #[must_use]
+    pub fn difference(&self, other: &Self) -> Self { self.combine(other, Self::difference_nodes) }
+
+    /// Toggle `variable_id` in every set of the family.
+    #[must_use]
+    pub fn change(&self, variable_id: usize) -> Self {
+        let mut zdd = self.clone();
+        zdd.variables = zdd.variables.max(variable_id + 1);
+        let root = zdd.change_node(self.root, variable_id, &mut HashMap::new());
+        zdd.rooted_at(root)
+    }
+
+    /// Sets that do not contain `variable_id`.
+    #[must_use]
+    pub fn subset0(&self, variable_id: usize) -> Self {
+        let mut zdd = self.clone();
+        let root = zdd.subset_node(self.root, variable_id, false, &mut HashMap::new());
+        zdd.rooted_at(root)
+    }
+
+    /// Sets that contain `variable_id`, with `variable_id` removed from each.
+    #[must_use]
+    pub fn subset1(&self, variable_id: usize) -> Self {
+        let mut zdd = self.clone();
+        let root = zdd.subset_node(self.root, variable_id, true, &mut HashMap::new());
+        zdd.rooted_at(root)
+    }
+
+    /// The number of sets in the family.
+    /// # Errors
+    /// * `EvaluationError` - the count does not fit in a `u128`
+    pub fn count(&self) -> Result<u128, FlowError> {
+        let mut counts: HashMap<usize, u128> = HashMap::new();
+        self.count_node(self.root, &mut counts)
+    }
+
+    fn combine(&self, other: &Self, op: fn(&mut Self, usize, usize, &mut Cache) -> usize) -> Self {
+        let mut zdd = self.clone();
+        zdd.variables = self.variables.max(other.variables);
+        let right = zdd.import(other, other.root, &mut HashMap::new());
+        let root = op(&mut zdd, self.root, right, &mut HashMap::new());
+        zdd.rooted_at(root)
+    }
+
+    pub(super) fn union_nodes(&mut self, p: usize, q: usize, cache: &mut Cache) -> usize {
+        if p == EMPTY || p == q {
+            return q;
+        }
+        if q == EMPTY {
+            return p;
+        }
+        let key = (p.min(q), p.max(q));
+        if let Some(&id) = cache.get(&key) {
+            return id;
+        }
+        let (p_top, q_top) = (self.top_variable(p), self.top_variable(q));
+        let (p_low, p_high) = self.children(p);
+        let (q_low, q_high) = self.children(q);
+        let id = if p_top < q_top {
+            let low = self.union_nodes(p_low, q, cache);
+            self.mk(p_top, low, p_high)
+        } else if q_top < p_top {
+            let low = self.union_nodes(p, q_low, cache);
+            self.mk(q_top, low, q_high)
+        } else {
+            let low = self.union_nodes(p_low, q_low, cache);
+            let high = self.union_nodes(p_high, q_high, cache);
+            self.mk(p_top, low, high)
+        };
+        cache.insert(key, id);
+        id
+    }
+
+    fn intersection_nodes(&mut self, p: usize, q: usize, cache: &mut Cache) -> usize {
+        if p == EMPTY || q == EMPTY {
+            return EMPTY;
+        }
+        if p == q {
+            return p;
+        }
+        let key = (p.min(q), p.max(q));
+        if let Some(&id) = cache.get(&key) {
+            return id;
+        }
+        let (p_top, q_top) = (self.top_variable(p), self.top_variable(q));
+        let (p_low, p_high) = self.children(p);
+        let (q_low, q_high) = self.children(q);
+        let id = if p_top < q_top {
+            self.intersection_nodes(p_low, q, cache)
+        } else if q_top < p_top {
+            self.intersection_nodes(p, q_low, cache)
+        } else {
+            let low = self.intersection_nodes(p_low, q_low, cache);
+            let high = self.intersection_nodes(p_high, q_high, cache);
+            self.mk(p_top, low, high)
+        };
+        cache.insert(key, id);
+        id
+    }
+
+    fn difference_nodes(&mut self, p: usize, q: usize, cache: &mut Cache) -> usize {
+        if p == EMPTY || p == q {
+            return EMPTY;
+        }
+        if q == EMPTY {
+            return p;
+        }
+        if let Some(&id) = cache.get(&(p, q)) {
+            return id;
+        }
+        let (p_top, q_top) = (self.top_variable(p), self.top_variable(q));
+        let (p_low, p_high) = self.children(p);
+        let (q_low, q_high) = self.children(q);
+        let id = if p_top < q_top {
+            let low = self.difference_nodes(p_low, q, cache);
+            self.mk(p_top, low, p_high)
+        } else if q_top < p_top {
+            self.difference_nodes(p, q_low, cache)
+        } else {
+            let low = self.difference_nodes(p_low, q_low, cache);
+            let high = self.difference_nodes(p_high, q_high, cache);
+            self.mk(p_top, low, high)
+        };
+        cache.insert((p, q), id);
+        id
+    }
+
+    fn change_node(
+        &mut self,
+        p: usize,
+        variable_id: usize,
+        cache: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if let Some(&id) = cache.get(&p) {
+            return id;
+        }
+        let top = self.top_variable(p);
+        let (low, high) = self.children(p);
+        let id = if top > variable_id {
+            self.mk(variable_id, EMPTY, p)
+        } else if top == variable_id {
+            self.mk(variable_id, high, low)
+        } else {
+            let low = self.change_node(low, variable_id, cache);
+            let high = self.change_node(high, variable_id, cache);
+            self.mk(top, low, high)
+        };
+        cache.insert(p, id);
+        id
+    }
+
+    fn subset_node(
+        &mut self,
+        p: usize,
+        variable_id: usize,
+        contains: bool,
+        cache: &mut HashMap<usize, usize>,
+    ) -> usize {
+        if let Some(&id) = cache.get(&p) {
+            return id;
+        }
+        let top = self.top_variable(p);
+        let (low, high) = self.children(p);
+        let id = if top > variable_id {
+            if contains {
+                EMPTY
+            } else {
+                p
+            }
+        } else if top == variable_id {
+            if contains {
+                high
+            } else {
+                low
+            }
+        } else {
+            let low = self.subset_node(low, variable_id, contains, cache);
+            let high = self.subset_node(high, variable_id, contains, cache);
+            self.mk(top, low, high)
+        };
+        cache.insert(p, id);
+        id
+    }
+
+    fn count_node(&self, p: usize, counts: &mut HashMap<usize, u128>) -> Result<u128, FlowError> {
+        if let Some(&count) = counts.get(&p) {
+            return Ok(count);
+        }
+        let count = match &self.nodes[p] {
+            ZddNode::Terminal(value) => u128::from(*value),
+            ZddNode::Decision(node) => self
+                .count_node(node.low, counts)?
+                .checked_add(self.count_node(node.high, counts)?)
+                .ok_or(EvaluationError("Set count does not fit in 128 bits"))?,
+        };
+        counts.insert(p, count);
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::zdd::ZeroSuppressedDecisionDiagram;
+
+    fn family(sets: &[Vec<usize>]) -> ZeroSuppressedDecisionDiagram {
+        ZeroSuppressedDecisionDiagram::from_sets(sets, 3)
+    }
+
+    #[test]
+    fn union() {
+        let left = family(&[vec![0], vec![1, 2]]);
+        let right = family(&[vec![1, 2], vec![]]);
+        let expected = family(&[vec![], vec![0], vec![1, 2]]);
+        assert_eq!(expected.sets(), left.union(&right).sets());
+    }
+
+    #[test]
+    fn intersection() {
+        let left = family(&[vec![0], vec![1, 2]]);
+        let right = family(&[vec![1, 2], vec![]]);
+        assert_eq!(vec![vec![1, 2]], left.intersection(&right).sets());
+    }
+
+    #[test]
+    fn difference() {
+        let left = family(&[vec![0], vec![1, 2]]);
+        let right = family(&[vec![1, 2], vec![]]);
+        assert_eq!(vec![vec![0]], left.difference(&right).sets());
+    }
+
+    #[test]
+    fn change() {
+        let zdd = family(&[vec![0], vec![1, 2]]);
+        let expected = family(&[vec![], vec![0, 1, 2]]);
+        assert_eq!(expected.sets(), zdd.change(0).sets());
+    }
+
+    #[test]
+    fn subsets() {
+        let zdd = family(&[vec![0], vec![0, 2], vec![1]]);
+        assert_eq!(vec![vec![1]], zdd.subset0(0).sets());
+        assert_eq!(vec![vec![], vec![2]], zdd.subset1(0).sets());
+    }
+
+    #[test]
+    fn count() {
+        assert_eq!(Ok(0), ZeroSuppressedDecisionDiagram::empty(2).count());
+        assert_eq!(Ok(1), ZeroSuppressedDecisionDiagram::base(2).count());
+        assert_eq!(Ok(2), family(&[vec![0], vec![1, 2]]).count());
+    }
+
+    #[test]
+    fn unused_nodes_dropped() {
+        let zdd = family(&[vec![0], vec![1, 2]]);
+        assert_eq!(2, zdd.subset1(0).node_count());
+    }
+}
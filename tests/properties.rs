@@ -0,0 +1,155 @@
+use flow::bdd::{AigerFormat, BinaryDecisionDiagram, BinaryOperator};
+use flow::crossbar::CrossbarMatrix;
+use flow::gen::{random_bdd, random_crossbar, BddOptions, CrossbarOptions, Order};
+use flow::Evaluate;
+use proptest::prelude::*;
+
+const OPERATORS: [BinaryOperator; 7] = [
+    BinaryOperator::And,
+    BinaryOperator::Or,
+    BinaryOperator::Xor,
+    BinaryOperator::Nand,
+    BinaryOperator::Nor,
+    BinaryOperator::Xnor,
+    BinaryOperator::Implies,
+];
+
+/// Variable `k` is bit `k` of `index`, matching `truth_table` indexing.
+fn assignment(index: usize, variables: usize) -> Vec<bool> {
+    (0..variables).map(|k| (index >> k) & 1 == 1).collect()
+}
+
+fn order() -> impl Strategy<Value = Order> {
+    prop_oneof![
+        Just(Order::Ascending),
+        Just(Order::Shuffled),
+        Just(Order::Unordered),
+    ]
+}
+
+/// Random diagrams of every shape the generator supports, including free and
+/// unreduced ones. Node counts the variables cannot hold are skipped.
+fn diagram() -> impl Strategy<Value = BinaryDecisionDiagram> {
+    (
+        1..=7usize,
+        1..=40usize,
+        order(),
+        any::<bool>(),
+        any::<u64>(),
+    )
+        .prop_filter_map(
+            "too many nodes for the variables",
+            |(variables, nodes, order, reduced, seed)| {
+                random_bdd(&BddOptions {
+                    variables,
+                    nodes,
+                    order,
+                    reduced,
+                    seed,
+                })
+                .ok()
+            },
+        )
+}
+
+fn crossbar() -> impl Strategy<Value = CrossbarMatrix> {
+    (
+        1..=8usize,
+        1..=8usize,
+        0..=12usize,
+        0.0..=1.0f64,
+        any::<u64>(),
+    )
+        .prop_map(|(rows, columns, variables, density, seed)| {
+            random_crossbar(&CrossbarOptions {
+                rows,
+                columns,
+                variables,
+                density,
+                seed,
+            })
+            .unwrap()
+        })
+}
+
+/// Two truth tables over the same number of variables.
+fn table_pair() -> impl Strategy<Value = (Vec<bool>, Vec<bool>)> {
+    (0..=6usize).prop_flat_map(|variables| {
+        let table = prop::collection::vec(any::<bool>(), 1 << variables);
+        (table.clone(), table)
+    })
+}
+
+proptest! {
+    #[test]
+    fn parse_display_round_trip(bdd in diagram()) {
+        let text = bdd.to_string();
+        let parsed: BinaryDecisionDiagram = text.parse().unwrap();
+        prop_assert_eq!(&text, &parsed.to_string());
+        prop_assert_eq!(bdd.truth_table().unwrap(), parsed.truth_table().unwrap());
+    }
+
+    #[test]
+    fn crossbar_round_trip(crossbar in crossbar()) {
+        let parsed: CrossbarMatrix = crossbar.to_string().parse().unwrap();
+        prop_assert_eq!(crossbar, parsed);
+    }
+
+    #[test]
+    fn reduce_preserves_truth_table(bdd in diagram()) {
+        let reduced = bdd.reduce().unwrap();
+        prop_assert_eq!(bdd.truth_table().unwrap(), reduced.truth_table().unwrap());
+        prop_assert_eq!(reduced.node_count(), reduced.reduce().unwrap().node_count());
+    }
+
+    #[test]
+    fn apply_matches_pointwise((left, right) in table_pair()) {
+        let left_bdd = BinaryDecisionDiagram::from_truth_table(&left).unwrap();
+        let right_bdd = BinaryDecisionDiagram::from_truth_table(&right).unwrap();
+        for operator in OPERATORS {
+            let expected: Vec<bool> = left
+                .iter()
+                .zip(&right)
+                .map(|(&l, &r)| operator.eval(l, r))
+                .collect();
+            let applied = left_bdd.apply(&right_bdd, operator).unwrap();
+            prop_assert_eq!(expected, applied.truth_table().unwrap(), "{:?}", operator);
+        }
+        let negated: Vec<bool> = left.iter().map(|&l| !l).collect();
+        prop_assert_eq!(negated, left_bdd.negate().unwrap().truth_table().unwrap());
+    }
+
+    /// Entry `index` of the truth table is the assignment whose variable `k`
+    /// is bit `k` of `index`.
+    #[test]
+    fn eval_agrees_with_truth_table(bdd in diagram()) {
+        let table = bdd.truth_table().unwrap();
+        prop_assert_eq!(1usize << bdd.variables(), table.len());
+        for (index, &value) in table.iter().enumerate() {
+            prop_assert_eq!(value, bdd.eval(&assignment(index, bdd.variables())).unwrap());
+        }
+    }
+
+    /// `eval` walks the diagram, so it is checked against two evaluators that
+    /// share none of that code: reachability on the synthesized crossbar, and
+    /// the diagram rebuilt by `apply` from its AIGER export.
+    #[test]
+    fn eval_agrees_with_independent_evaluators(bdd in diagram()) {
+        let crossbar = BinaryDecisionDiagram::to_crossbar(std::slice::from_ref(&bdd)).unwrap();
+        let aiger = BinaryDecisionDiagram::to_aiger(std::slice::from_ref(&bdd), AigerFormat::Ascii)
+            .unwrap();
+        let rebuilt = BinaryDecisionDiagram::from_aiger(&aiger).unwrap().remove(0);
+        for index in 0..1usize << bdd.variables() {
+            let values = assignment(index, bdd.variables());
+            let value = bdd.eval(&values).unwrap();
+            prop_assert_eq!(Ok(vec![value]), crossbar.eval_outputs(&values), "{:?}", values);
+            prop_assert_eq!(Ok(value), rebuilt.eval(&values), "{:?}", values);
+        }
+    }
+
+    #[test]
+    fn truth_table_round_trip((table, _) in table_pair()) {
+        let bdd = BinaryDecisionDiagram::from_truth_table(&table).unwrap();
+        prop_assert_eq!(table, bdd.truth_table().unwrap());
+    }
+}